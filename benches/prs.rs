@@ -0,0 +1,166 @@
+// Compares the PRS decompressors with the original implementation that seeks backwards in its destination.
+// Run with `cargo bench`.
+#![allow(dead_code)]
+
+#[path = "../src/prs.rs"]
+mod prs;
+#[path = "../src/test_data.rs"]
+mod test_data;
+
+use std::io::{self, Cursor, Read, Write, Seek, SeekFrom};
+use std::time::{Duration, Instant};
+
+const ITERATIONS: u32 = 20;
+
+fn main() {
+    // Roughly the size of a large .dat file, with the mix of repetition and noise typical for quest data.
+    let data: Vec<u8> = test_data::noise(1_000_000, 0x2545F491)
+        .into_iter()
+        .enumerate()
+        .map(|(i, noise)| if noise % 8 == 0 { noise } else { (i / 72 % 29) as u8 })
+        .collect();
+
+    let mut compressed = Vec::new();
+    prs::compress(&mut Cursor::new(&data[..]), &mut compressed, prs::Compression::Fast).unwrap();
+    println!("{} bytes compressed to {} bytes", data.len(), compressed.len());
+
+    bench("seeking decompress", &data, || {
+        let mut dst = Cursor::new(Vec::with_capacity(data.len()));
+        seeking::decompress(&mut Cursor::new(&compressed[..]), &mut dst).unwrap();
+        return dst.into_inner();
+    });
+
+    bench("decompress into Vec", &data, || {
+        let mut dst = Vec::with_capacity(data.len());
+        prs::decompress(&mut Cursor::new(&compressed[..]), &mut dst, prs::DEFAULT_MAX_SIZE).unwrap();
+        return dst;
+    });
+
+    bench("decompress_buf", &data, || prs::decompress_buf(&compressed, prs::DEFAULT_MAX_SIZE).unwrap().0);
+}
+
+fn bench<F: FnMut() -> Vec<u8>>(name: &str, expected: &Vec<u8>, mut f: F) {
+    assert!(&f() == expected, "{} produced wrong output", name);
+
+    let start = Instant::now();
+
+    for _ in 0..ITERATIONS {
+        f();
+    }
+
+    let per_iteration = start.elapsed() / ITERATIONS;
+    let mb_per_second = expected.len() as f64 / seconds(per_iteration) / 1e6;
+    println!("{:<24} {:>10.3} ms/iter {:>10.1} MB/s", name, seconds(per_iteration) * 1e3, mb_per_second);
+}
+
+fn seconds(duration: Duration) -> f64 {
+    return duration.as_secs() as f64 + duration.subsec_nanos() as f64 * 1e-9;
+}
+
+// The original decompressor, which resolves back-references by seeking in and reading from its destination.
+mod seeking {
+    use super::*;
+
+    pub fn decompress<S: Read, D: Read + Write + Seek>(src: &mut S, dst: &mut D) -> io::Result<()> {
+        let mut cxt = Context {
+            src: src,
+            dst: dst,
+            flags: 0,
+            bit_pos: 0,
+            buf: [0; 256]
+        };
+
+        loop {
+            if cxt.read_flag_bit()? == 1 {
+                cxt.copy_byte()?;
+            } else {
+                let mut size: u16;
+                let mut offset: i16;
+
+                if cxt.read_flag_bit()? == 0 {
+                    size = (cxt.read_flag_bit()? as u16) << 1;
+                    size |= cxt.read_flag_bit()? as u16;
+                    size += 2;
+                    offset = cxt.read_byte()? as i16 - 256;
+                } else {
+                    offset = cxt.read_short()? as i16;
+
+                    if offset == 0 {
+                        return Ok(());
+                    }
+
+                    size = offset as u16 & 0b111;
+                    offset = ((offset as u16) >> 3) as i16;
+
+                    if size == 0 {
+                        size = cxt.read_byte()? as u16;
+                        size += 1;
+                    } else {
+                        size += 2;
+                    }
+
+                    offset -= 8192;
+                }
+
+                cxt.offset_copy(offset, size)?;
+            }
+        }
+    }
+
+    struct Context<'a, S: 'a + Read, D: 'a + Read + Write + Seek> {
+        src: &'a mut S,
+        dst: &'a mut D,
+        flags: u8,
+        bit_pos: u8,
+        buf: [u8; 256]
+    }
+
+    impl<'a, S: Read, D: Read + Write + Seek> Context<'a, S, D> {
+        fn read_flag_bit(&mut self) -> io::Result<u8> {
+            if self.bit_pos == 0 {
+                self.src.read_exact(&mut self.buf[0..1])?;
+                self.flags = self.buf[0];
+                self.bit_pos = 8;
+            }
+
+            let rv = (self.flags) & 1;
+            self.flags >>= 1;
+            self.bit_pos -= 1;
+            return Ok(rv);
+        }
+
+        fn copy_byte(&mut self) -> io::Result<()> {
+            self.src.read_exact(&mut self.buf[0..1])?;
+            self.dst.write_all(&self.buf[0..1])?;
+            return Ok(());
+        }
+
+        fn read_byte(&mut self) -> io::Result<u8> {
+            self.src.read_exact(&mut self.buf[0..1])?;
+            return Ok(self.buf[0]);
+        }
+
+        fn read_short(&mut self) -> io::Result<u16> {
+            self.src.read_exact(&mut self.buf[0..2])?;
+            return Ok(((self.buf[1] as u16) << 8) | (self.buf[0] as u16));
+        }
+
+        fn offset_copy(&mut self, offset: i16, size: u16) -> io::Result<()> {
+            let offset = offset as i64;
+            let size = size as usize;
+            let buf_size = ::std::cmp::min(-offset as usize, size);
+            let buf = &mut self.buf[0..buf_size];
+            self.dst.seek(SeekFrom::Current(offset))?;
+            self.dst.read_exact(buf)?;
+            self.dst.seek(SeekFrom::Current(-offset - buf_size as i64))?;
+
+            for _ in 0..(size / buf_size) {
+                self.dst.write_all(buf)?;
+            }
+
+            self.dst.write_all(&buf[0..(size % buf_size)])?;
+
+            return Ok(());
+        }
+    }
+}
@@ -4,18 +4,30 @@ extern crate csv;
 extern crate clap;
 
 mod read;
+mod write;
 mod prs;
+mod script;
 mod types;
 mod util;
+#[cfg(test)]
+mod test_data;
 
 use std::cmp::max;
-use std::fs::File;
-use std::io::{self, Write, BufReader};
+use std::collections::{BTreeMap, HashSet};
+use std::fmt::Display;
+use std::fs::{self, File};
+use std::io::{self, Cursor, Read, Write, BufReader, BufWriter};
 use std::iter::Iterator;
 use std::path::{Path, PathBuf};
-use clap::{App, Arg};
-use read::{quest, ReadError};
-use types::{Quest, MonsterType};
+use std::process;
+use clap::{App, AppSettings, Arg, ArgMatches, SubCommand};
+use prs::Compression;
+use read::{bin, dat, qst, quest, ReadError};
+use script::{asm, cfg, disasm, text, validate, Script};
+use script::text::Text;
+use types::{EventAction, Language, Quest, QuestType, MonsterType, TextMode};
+use util::TextDecoder;
+use write::qst::QstOptions;
 
 fn main() {
     let matches = App::new(env!("CARGO_PKG_NAME"))
@@ -27,17 +39,223 @@ fn main() {
             .short("c")
             .takes_value(false)
             .help("Output information in CSV format"))
+        .arg(Arg::with_name("long")
+            .long("long")
+            .short("l")
+            .takes_value(false)
+            .requires("csv")
+            .help("Output a CSV row per quest, area and monster type"))
+        .arg(Arg::with_name("strict")
+            .long("strict")
+            .takes_value(false)
+            .help("Fail on invalid text instead of replacing it"))
         .arg(Arg::with_name("INPUT")
             .required(true)
             .multiple(true)
             .help("Files and/or directories to process"))
+        .setting(AppSettings::SubcommandsNegateReqs)
+        .subcommand(SubCommand::with_name("extract")
+            .about("Writes the .dat and .bin files embedded in .qst files to disk")
+            .arg(Arg::with_name("decompress")
+                .long("decompress")
+                .short("d")
+                .takes_value(false)
+                .help("Write decompressed files instead of PRS compressed files"))
+            .arg(Arg::with_name("output")
+                .long("output")
+                .short("o")
+                .takes_value(true)
+                .value_name("DIR")
+                .help("Directory to write to, defaults to the directory of each .qst file"))
+            .arg(Arg::with_name("INPUT")
+                .required(true)
+                .multiple(true)
+                .help(".qst files and/or directories to process")))
+        .subcommand(SubCommand::with_name("pack")
+            .about("Packs a .dat and .bin file into a .qst file")
+            .arg(Arg::with_name("output")
+                .long("output")
+                .short("o")
+                .takes_value(true)
+                .required(true)
+                .value_name("FILE")
+                .help("File to write the .qst file to"))
+            .arg(Arg::with_name("name")
+                .long("name")
+                .short("n")
+                .takes_value(true)
+                .value_name("NAME")
+                .help("Name of the embedded files without extension, defaults to the name of the output file"))
+            .arg(Arg::with_name("download")
+                .long("download")
+                .short("d")
+                .takes_value(false)
+                .help("Write a download quest instead of an online quest"))
+            .arg(Arg::with_name("best")
+                .long("best")
+                .takes_value(false)
+                .help("Compress better at the cost of speed"))
+            .arg(Arg::with_name("DAT")
+                .required(true)
+                .help(".dat file to pack, may be PRS compressed"))
+            .arg(Arg::with_name("BIN")
+                .required(true)
+                .help(".bin file to pack, may be PRS compressed")))
+        .subcommand(SubCommand::with_name("disasm")
+            .about("Writes the script of a quest as an assembly listing")
+            .arg(Arg::with_name("output")
+                .long("output")
+                .short("o")
+                .takes_value(true)
+                .value_name("FILE")
+                .help("File to write to, defaults to standard output"))
+            .arg(Arg::with_name("INPUT")
+                .required(true)
+                .help(".qst or .bin file to disassemble")))
+        .subcommand(SubCommand::with_name("asm")
+            .about("Assembles an assembly listing into a .bin file")
+            .arg(Arg::with_name("output")
+                .long("output")
+                .short("o")
+                .takes_value(true)
+                .value_name("FILE")
+                .help("File to write to, defaults to the input file with a .bin extension"))
+            .arg(Arg::with_name("INPUT")
+                .required(true)
+                .help("Assembly listing to assemble")))
+        .subcommand(SubCommand::with_name("events")
+            .about("Lists the events of a quest with their waves, delays and actions")
+            .arg(Arg::with_name("INPUT")
+                .required(true)
+                .help(".qst or .dat file to list")))
+        .subcommand(SubCommand::with_name("cfg")
+            .about("Writes the control-flow graph of the script of a quest in the Graphviz DOT language")
+            .arg(Arg::with_name("function")
+                .long("function")
+                .short("f")
+                .takes_value(true)
+                .value_name("LABEL")
+                .help("Label of the function to graph by basic block, defaults to a graph of all functions"))
+            .arg(Arg::with_name("output")
+                .long("output")
+                .short("o")
+                .takes_value(true)
+                .value_name("FILE")
+                .help("File to write to, defaults to standard output"))
+            .arg(Arg::with_name("INPUT")
+                .required(true)
+                .help(".qst or .bin file to graph")))
+        .subcommand(SubCommand::with_name("validate-script")
+            .about("Checks the script of a quest for invalid instructions, labels and arguments")
+            .arg(Arg::with_name("INPUT")
+                .required(true)
+                .help(".qst or .bin file to check")))
+        .subcommand(SubCommand::with_name("export-text")
+            .about("Writes the quest name, descriptions and script strings of a quest to a CSV file for translation")
+            .arg(Arg::with_name("output")
+                .long("output")
+                .short("o")
+                .takes_value(true)
+                .value_name("FILE")
+                .help("File to write to, defaults to standard output"))
+            .arg(Arg::with_name("INPUT")
+                .required(true)
+                .help(".qst or .bin file to export")))
+        .subcommand(SubCommand::with_name("import-text")
+            .about("Writes a .bin file with the translations of a CSV file written by export-text")
+            .arg(Arg::with_name("output")
+                .long("output")
+                .short("o")
+                .takes_value(true)
+                .required(true)
+                .value_name("FILE")
+                .help("File to write the uncompressed .bin file to"))
+            .arg(Arg::with_name("language")
+                .long("language")
+                .short("l")
+                .takes_value(true)
+                .value_name("LANGUAGE")
+                .help("Language code to set, Dreamcast and GameCube quests are encoded according to it"))
+            .arg(Arg::with_name("INPUT")
+                .required(true)
+                .help(".qst or .bin file to translate"))
+            .arg(Arg::with_name("TRANSLATIONS")
+                .required(true)
+                .help("CSV file with translations")))
         .get_matches();
 
+    if let Some(matches) = matches.subcommand_matches("extract") {
+        extract(matches);
+        return;
+    }
+
+    if let Some(matches) = matches.subcommand_matches("pack") {
+        if let Err(err) = pack(matches) {
+            writeln!(&mut io::stderr(), "Couldn't pack {}: {}", matches.value_of("output").unwrap(), err).unwrap();
+            process::exit(1);
+        }
+        return;
+    }
+
+    if let Some(matches) = matches.subcommand_matches("disasm") {
+        if let Err(err) = disasm(matches) {
+            writeln!(&mut io::stderr(), "Couldn't disassemble {}: {}", matches.value_of("INPUT").unwrap(), err)
+                .unwrap();
+            process::exit(1);
+        }
+        return;
+    }
+
+    if let Some(matches) = matches.subcommand_matches("asm") {
+        asm(matches);
+        return;
+    }
+
+    if let Some(matches) = matches.subcommand_matches("events") {
+        let input = Path::new(matches.value_of("INPUT").unwrap());
+
+        match read_file(input, TextMode::Lenient) {
+            Ok(quest) => print_events(&quest),
+            Err(err) => {
+                writeln!(&mut io::stderr(), "Couldn't read {}: {}", input.display(), err).unwrap();
+                process::exit(1);
+            }
+        }
+        return;
+    }
+
+    if let Some(matches) = matches.subcommand_matches("cfg") {
+        if let Err(err) = cfg(matches) {
+            writeln!(&mut io::stderr(), "Couldn't graph {}: {}", matches.value_of("INPUT").unwrap(), err).unwrap();
+            process::exit(1);
+        }
+        return;
+    }
+
+    if let Some(matches) = matches.subcommand_matches("validate-script") {
+        validate_script(matches);
+        return;
+    }
+
+    if let Some(matches) = matches.subcommand_matches("export-text") {
+        if let Err(err) = export_text(matches) {
+            writeln!(&mut io::stderr(), "Couldn't export {}: {}", matches.value_of("INPUT").unwrap(), err).unwrap();
+            process::exit(1);
+        }
+        return;
+    }
+
+    if let Some(matches) = matches.subcommand_matches("import-text") {
+        import_text(matches);
+        return;
+    }
+
     let files: Vec<&str> = matches.values_of("INPUT").unwrap().collect();
     let files: Vec<&Path> = files.iter().map(|&arg| Path::new(arg)).collect();
 
+    let text_mode = if matches.is_present("strict") { TextMode::Strict } else { TextMode::Lenient };
     let mut quest_results = Vec::new();
-    read_quests(&files, &mut quest_results);
+    read_quests(&files, text_mode, &mut quest_results, &mut HashSet::new());
 
     let mut quests = Vec::new();
     let mut errors = Vec::new();
@@ -50,7 +268,9 @@ fn main() {
     }
 
     if matches.is_present("csv") {
-        if quests_to_csv(&quests).is_err() {
+        let result = if matches.is_present("long") { quests_to_long_csv(&quests) } else { quests_to_csv(&quests) };
+
+        if result.is_err() {
             writeln!(&mut io::stderr(), "CSV generation failed.").unwrap();
         }
     } else {
@@ -58,10 +278,21 @@ fn main() {
     }
 }
 
-fn read_quests(files: &Vec<&Path>, quests: &mut Vec<read::Result<Quest>>) {
+// Standalone .bin and .dat files with the same name are read as one quest, read_files keeps track of the files that
+// have been read so the other file of a pair isn't read again.
+// Invalid text is reported on stderr, other errors are usually files that aren't quests.
+fn read_quests(files: &Vec<&Path>, text_mode: TextMode, quests: &mut Vec<read::Result<Quest>>,
+               read_files: &mut HashSet<PathBuf>) {
     for file in files {
-        if file.is_file() {
-            quests.push(read_file(file));
+        if file.is_file() && read_files.insert(canonical_path(file)) {
+            match bin_dat_pair(file) {
+                Some((bin_file, dat_file)) => {
+                    read_files.insert(canonical_path(&bin_file));
+                    read_files.insert(canonical_path(&dat_file));
+                    quests.push(report_encoding_error(file, read_pair(&bin_file, &dat_file, text_mode)));
+                },
+                None => quests.push(report_encoding_error(file, read_file(file, text_mode)))
+            }
         }
     }
 
@@ -71,7 +302,7 @@ fn read_quests(files: &Vec<&Path>, quests: &mut Vec<read::Result<Quest>>) {
                 Ok(sub_files) => {
                     let sub_paths: Vec<PathBuf> = sub_files.filter_map(|sf| sf.ok()).map(|sf| sf.path()).collect();
                     let sub_paths = sub_paths.iter().map(|sp| sp.as_path()).collect();
-                    read_quests(&sub_paths, quests);
+                    read_quests(&sub_paths, text_mode, quests, read_files);
                 },
                 Err(err) => quests.push(Err(ReadError::from(err)))
             }
@@ -79,10 +310,376 @@ fn read_quests(files: &Vec<&Path>, quests: &mut Vec<read::Result<Quest>>) {
     }
 }
 
-fn read_file(file_name: &Path) -> read::Result<Quest> {
+fn read_file(file_name: &Path, text_mode: TextMode) -> read::Result<Quest> {
     let file = File::open(file_name)?;
     let mut buf_reader = BufReader::new(file);
-    return quest::read(&mut buf_reader);
+    return quest::read(&mut buf_reader, text_mode);
+}
+
+fn read_pair(bin_file_name: &Path, dat_file_name: &Path, text_mode: TextMode) -> read::Result<Quest> {
+    let mut bin_reader = BufReader::new(File::open(bin_file_name)?);
+    let mut dat_reader = BufReader::new(File::open(dat_file_name)?);
+    return quest::read_pair(&mut bin_reader, &mut dat_reader, text_mode);
+}
+
+fn report_encoding_error(file: &Path, result: read::Result<Quest>) -> read::Result<Quest> {
+    if let Err(ReadError::Encoding { field, offset }) = result {
+        writeln!(&mut io::stderr(), "Couldn't read {}: invalid text in the {} at offset {:#x}.", file.display(), field,
+                 offset).unwrap();
+    }
+
+    return result;
+}
+
+fn canonical_path(file: &Path) -> PathBuf {
+    return file.canonicalize().unwrap_or(file.to_path_buf());
+}
+
+// Returns the .bin and .dat file names when file is part of a pair of standalone quest files that both exist.
+fn bin_dat_pair(file: &Path) -> Option<(PathBuf, PathBuf)> {
+    let extension = match file.extension().and_then(|e| e.to_str()) {
+        Some(extension) => extension,
+        None => return None
+    };
+    let upper_case = extension.chars().all(|c| c.is_uppercase());
+
+    let (bin_file, dat_file) = match &extension.to_lowercase()[..] {
+        "bin" => (file.to_path_buf(), file.with_extension(if upper_case { "DAT" } else { "dat" })),
+        "dat" => (file.with_extension(if upper_case { "BIN" } else { "bin" }), file.to_path_buf()),
+        _ => return None
+    };
+
+    if bin_file.is_file() && dat_file.is_file() {
+        return Some((bin_file, dat_file));
+    } else {
+        return None;
+    }
+}
+
+// Exits with status 1 when a file couldn't be extracted, after extracting the other files.
+fn extract(matches: &ArgMatches) {
+    let files: Vec<&Path> = matches.values_of("INPUT").unwrap().map(|arg| Path::new(arg)).collect();
+    let output_dir = matches.value_of("output").map(|arg| Path::new(arg));
+
+    if !extract_files(&files, output_dir, matches.is_present("decompress"), true) {
+        process::exit(1);
+    }
+}
+
+// Extracts the files embedded in .qst files. Files in directories are only extracted when they have a .qst extension,
+// files that were explicitly passed are always extracted. Errors are reported on stderr, returns false when there
+// were any.
+fn extract_files(files: &Vec<&Path>, output_dir: Option<&Path>, decompress: bool, explicit: bool) -> bool {
+    let mut success = true;
+
+    for file in files {
+        if file.is_file() && (explicit || has_extension(file, "qst")) {
+            if let Err(err) = extract_file(file, output_dir, decompress) {
+                writeln!(&mut io::stderr(), "Couldn't extract {}: {}", file.display(), err).unwrap();
+                success = false;
+            }
+        } else if file.is_dir() {
+            match file.read_dir() {
+                Ok(sub_files) => {
+                    let sub_paths: Vec<PathBuf> = sub_files.filter_map(|sf| sf.ok()).map(|sf| sf.path()).collect();
+                    let sub_paths = sub_paths.iter().map(|sp| sp.as_path()).collect();
+                    success &= extract_files(&sub_paths, output_dir, decompress, false);
+                },
+                Err(err) => {
+                    writeln!(&mut io::stderr(), "Couldn't read {}: {}", file.display(), err).unwrap();
+                    success = false;
+                }
+            }
+        }
+    }
+
+    return success;
+}
+
+fn extract_file(file_name: &Path, output_dir: Option<&Path>, decompress: bool) -> read::Result<()> {
+    let mut buf_reader = BufReader::new(File::open(file_name)?);
+    let (_, headers, file_data) = qst::extract(&mut buf_reader, &mut TextDecoder::new(TextMode::Lenient))?;
+    let output_dir = output_dir.or(file_name.parent()).unwrap_or(Path::new("."));
+    fs::create_dir_all(output_dir)?;
+
+    for (header, data) in headers.iter().zip(file_data.iter()) {
+        // Only use the last component of the embedded file name, so files can't be written outside of output_dir.
+        let embedded_name = match Path::new(&header.file_name).file_name() {
+            Some(embedded_name) => embedded_name,
+            None => return Err(ReadError::InvalidData)
+        };
+        let path = output_dir.join(embedded_name);
+        let mut writer = BufWriter::new(File::create(&path)?);
+
+        if decompress {
+            prs::decompress(&mut &data[..], &mut writer, prs::DEFAULT_MAX_SIZE)?;
+        } else {
+            writer.write_all(data)?;
+        }
+
+        writer.flush()?;
+        println!("{}", path.display());
+    }
+
+    return Ok(());
+}
+
+// The version, quest number, quest name and language are taken from the .bin file.
+fn pack(matches: &ArgMatches) -> Result<(), String> {
+    let output = Path::new(matches.value_of("output").unwrap());
+    let read_data = |arg: &str, read: fn(&mut BufReader<File>) -> read::Result<Vec<u8>>| {
+        File::open(arg)
+            .map_err(ReadError::from)
+            .and_then(|file| read(&mut BufReader::new(file)))
+            .map_err(|err| format!("couldn't read {}: {}", arg, err))
+    };
+    let dat_data = read_data(matches.value_of("DAT").unwrap(), quest::read_dat_data)?;
+    let bin_data = read_data(matches.value_of("BIN").unwrap(), quest::read_bin_data)?;
+    let version = bin::detect_version(&bin_data).ok_or("invalid .bin file".to_string())?;
+    let bin_file = bin::read(&mut Cursor::new(&bin_data), version, &mut TextDecoder::new(TextMode::Lenient))
+        .map_err(|err| format!("{}", err))?;
+    let file_name = match matches.value_of("name") {
+        Some(name) => name.to_string(),
+        None => output.file_stem().map(|stem| stem.to_string_lossy().into_owned()).unwrap_or("quest".to_string())
+    };
+    let options = QstOptions {
+        version: version,
+        quest_type: if matches.is_present("download") { QuestType::Download } else { QuestType::Online },
+        quest_number: bin_file.quest_number,
+        quest_name: bin_file.quest_name,
+        language: bin_file.language,
+        file_name: file_name,
+        compression: if matches.is_present("best") { Compression::Best } else { Compression::Fast }
+    };
+
+    let mut writer = BufWriter::new(File::create(output).map_err(|err| err.to_string())?);
+    write::qst::write(&mut writer, &options, &dat_data, &bin_data).map_err(|err| format!("{}", err))?;
+    return writer.flush().map_err(|err| err.to_string());
+}
+
+fn disasm(matches: &ArgMatches) -> read::Result<()> {
+    let script = read_script(Path::new(matches.value_of("INPUT").unwrap()))?;
+
+    match matches.value_of("output") {
+        Some(output) => {
+            let mut writer = BufWriter::new(File::create(output)?);
+            disasm::disassemble(&script, &mut writer)?;
+            writer.flush()?;
+        },
+        None => {
+            let stdout = io::stdout();
+            disasm::disassemble(&script, &mut stdout.lock())?;
+        }
+    }
+
+    return Ok(());
+}
+
+// Writes an uncompressed .bin file, errors are reported on stderr and exit with status 1.
+fn asm(matches: &ArgMatches) {
+    let input = Path::new(matches.value_of("INPUT").unwrap());
+    let output = matches.value_of("output").map(PathBuf::from).unwrap_or(input.with_extension("bin"));
+    let mut text = String::new();
+
+    if let Err(err) = File::open(input).and_then(|mut file| file.read_to_string(&mut text)) {
+        writeln!(&mut io::stderr(), "Couldn't read {}: {}", input.display(), err).unwrap();
+        process::exit(1);
+    }
+
+    let script = match asm::assemble(&text) {
+        Ok(script) => script,
+        Err(err) => {
+            writeln!(&mut io::stderr(), "Couldn't assemble {}: {}", input.display(), err).unwrap();
+            process::exit(1);
+        }
+    };
+
+    let data = match script::write(&script) {
+        Ok(data) => data,
+        Err(err) => {
+            writeln!(&mut io::stderr(), "Couldn't assemble {}: {}", input.display(), err).unwrap();
+            process::exit(1);
+        }
+    };
+
+    if let Err(err) = File::create(&output).and_then(|mut file| file.write_all(&data)) {
+        writeln!(&mut io::stderr(), "Couldn't write {}: {}", output.display(), err).unwrap();
+        process::exit(1);
+    }
+}
+
+fn cfg(matches: &ArgMatches) -> Result<(), String> {
+    let script = read_script(Path::new(matches.value_of("INPUT").unwrap())).map_err(|err| format!("{}", err))?;
+
+    let graph = match matches.value_of("function") {
+        Some(label) => {
+            let label = label.parse().map_err(|_| format!("invalid label {}", label))?;
+            cfg::function_graph(&script, label).ok_or(format!("no code has label {}", label))?
+        },
+        None => cfg::quest_graph(&script)
+    };
+
+    let result = match matches.value_of("output") {
+        Some(output) => File::create(output).and_then(|mut file| cfg::write_dot(&graph, &mut file)),
+        None => cfg::write_dot(&graph, &mut io::stdout())
+    };
+
+    return result.map_err(|err| err.to_string());
+}
+
+// Prints a line per problem, exits with status 1 when there are problems other than warnings.
+fn validate_script(matches: &ArgMatches) {
+    let input = Path::new(matches.value_of("INPUT").unwrap());
+    let entry_labels = dat_script_labels(input);
+    let result = File::open(input)
+        .map_err(ReadError::from)
+        .and_then(|file| quest::read_bin_data(&mut BufReader::new(file)))
+        .and_then(|bin_data| validate::validate(&bin_data, &entry_labels));
+
+    let problems = match result {
+        Ok(problems) => problems,
+        Err(err) => {
+            writeln!(&mut io::stderr(), "Couldn't validate {}: {}", input.display(), err).unwrap();
+            process::exit(2);
+        }
+    };
+
+    for problem in &problems {
+        let label = problem.label.map(|label| label.to_string()).unwrap_or("none".to_string());
+        let warning = if problem.kind.is_warning() { "warning: " } else { "" };
+        println!("{:#06x} (label {}): {}{}", problem.offset, label, warning, problem.kind);
+    }
+
+    if problems.iter().any(|problem| !problem.kind.is_warning()) {
+        process::exit(1);
+    }
+}
+
+// Returns the script labels of the NPCs and the functions called by events in the .dat file of a .qst file or in the
+// .dat file next to a .bin file. Quests without a readable .dat file have none.
+fn dat_script_labels(input: &Path) -> Vec<u16> {
+    let dat_path = bin_dat_pair(input).map(|(_, dat_file)| dat_file).unwrap_or(input.to_path_buf());
+    let dat_file = File::open(dat_path)
+        .map_err(ReadError::from)
+        .and_then(|file| quest::read_dat_data(&mut BufReader::new(file)))
+        .and_then(|dat_data| dat::read(&mut Cursor::new(dat_data), None));
+
+    let dat_file = match dat_file {
+        Ok(dat_file) => dat_file,
+        Err(_) => return Vec::new()
+    };
+    let npc_labels = dat_file.npcs.iter().map(|npc| npc.script_label);
+    let event_labels = dat_file.events.iter()
+        .flat_map(|event| event.actions.iter())
+        .filter_map(|action| match *action {
+            EventAction::CallFunction { label } => Some(label),
+            _ => None
+        });
+
+    return npc_labels.chain(event_labels)
+        .filter(|&label| label <= u16::max_value() as u32)
+        .map(|label| label as u16)
+        .collect();
+}
+
+fn export_text(matches: &ArgMatches) -> Result<(), String> {
+    let script = read_script(Path::new(matches.value_of("INPUT").unwrap())).map_err(|err| format!("{}", err))?;
+
+    let result = match matches.value_of("output") {
+        Some(output) => write_texts(&script, File::create(output).map_err(|err| err.to_string())?),
+        None => write_texts(&script, io::stdout())
+    };
+
+    return result.map_err(|err| err.to_string());
+}
+
+// Writes a CSV file with an ID, original and translation column, the translation column is left empty.
+fn write_texts<W: Write>(script: &Script, dst: W) -> csv::Result<()> {
+    let mut writer = csv::Writer::from_writer(dst);
+    writer.encode(("ID", "Original", "Translation"))?;
+
+    for text in text::export(script) {
+        writer.encode((text.id, text.text, ""))?;
+    }
+
+    return writer.flush();
+}
+
+// Reads a CSV file written by export_text, rows with an empty translation keep the original string. Errors are
+// reported on stderr and exit with status 1.
+fn import_text(matches: &ArgMatches) {
+    let input = Path::new(matches.value_of("INPUT").unwrap());
+    let translations = Path::new(matches.value_of("TRANSLATIONS").unwrap());
+    let output = Path::new(matches.value_of("output").unwrap());
+
+    let mut script = match read_script(input) {
+        Ok(script) => script,
+        Err(err) => {
+            writeln!(&mut io::stderr(), "Couldn't read {}: {}", input.display(), err).unwrap();
+            process::exit(1);
+        }
+    };
+
+    if let Some(language) = matches.value_of("language") {
+        match language.parse::<u8>() {
+            Ok(language) => text::set_language(&mut script, Language::from(language)),
+            Err(_) => {
+                writeln!(&mut io::stderr(), "Invalid language {}.", language).unwrap();
+                process::exit(1);
+            }
+        }
+    }
+
+    let texts = match read_translations(translations) {
+        Ok(texts) => texts,
+        Err(err) => {
+            writeln!(&mut io::stderr(), "Couldn't read {}: {}", translations.display(), err).unwrap();
+            process::exit(1);
+        }
+    };
+
+    if let Err(err) = text::import(&mut script, &texts) {
+        writeln!(&mut io::stderr(), "Couldn't import {}: {}", translations.display(), err).unwrap();
+        process::exit(1);
+    }
+
+    let data = match script::write(&script) {
+        Ok(data) => data,
+        Err(err) => {
+            writeln!(&mut io::stderr(), "Couldn't translate {}: {}", input.display(), err).unwrap();
+            process::exit(1);
+        }
+    };
+
+    if let Err(err) = File::create(output).and_then(|mut file| file.write_all(&data)) {
+        writeln!(&mut io::stderr(), "Couldn't write {}: {}", output.display(), err).unwrap();
+        process::exit(1);
+    }
+}
+
+fn read_translations(file_name: &Path) -> csv::Result<Vec<Text>> {
+    let mut reader = csv::Reader::from_file(file_name)?.has_headers(true);
+    let mut texts = Vec::new();
+
+    for row in reader.decode() {
+        let (id, _, translation): (String, String, String) = row?;
+
+        if !translation.is_empty() {
+            texts.push(Text { id: id, text: translation });
+        }
+    }
+
+    return Ok(texts);
+}
+
+fn read_script(file_name: &Path) -> read::Result<Script> {
+    let mut buf_reader = BufReader::new(File::open(file_name)?);
+    let bin_data = quest::read_bin_data(&mut buf_reader)?;
+    return script::read(&bin_data);
+}
+
+fn has_extension(file: &Path, extension: &str) -> bool {
+    return file.extension().and_then(|e| e.to_str()).map(|e| e.to_lowercase() == extension).unwrap_or(false);
 }
 
 fn quests_to_csv(quests: &Vec<Quest>) -> csv::Result<()> {
@@ -93,7 +690,7 @@ fn quests_to_csv(quests: &Vec<Quest>) -> csv::Result<()> {
         .collect();
     writer.encode(("Quest", "Short Description", "Episode", monster_types))?;
 
-    for &Quest { ref name, ref short_description, ref episode, ref monster_counts } in quests {
+    for &Quest { ref name, ref short_description, ref episode, ref monster_counts, .. } in quests {
         let mut record = (name, short_description, episode.to_string(), vec![0; MonsterType::Shambertin as usize + 1]);
 
         for (monster_type, count) in monster_counts {
@@ -106,19 +703,135 @@ fn quests_to_csv(quests: &Vec<Quest>) -> csv::Result<()> {
     Ok(())
 }
 
+// Writes a row per monster type of every area.
+fn quests_to_long_csv(quests: &Vec<Quest>) -> csv::Result<()> {
+    let mut writer = csv::Writer::from_writer(io::stdout());
+    writer.encode(("Quest", "Episode", "Area", "Monster", "Count"))?;
+
+    for quest in quests {
+        for (area, counts) in &quest.area_monster_counts {
+            for (monster_type, count) in counts {
+                writer.encode((&quest.name, quest.episode.to_string(), area, monster_type.to_string(), count))?;
+            }
+        }
+    }
+
+    Ok(())
+}
+
 fn print_quests(quests: &Vec<Quest>) {
-    for &Quest { ref name, ref short_description, ref episode, ref monster_counts } in quests {
-        println!("Name: {}\nDetected episode {:?}.\nShort description:\n\n{}", name, episode, short_description);
+    for quest in quests {
+        let &Quest { ref version, ref name, ref short_description, ref long_description, ref episode,
+                     ref episode_source, ref monster_counts, .. } = quest;
+        let version = match *version {
+            Some(_) if quest.gamecube => "GameCube".to_string(),
+            Some(version) => version.to_string(),
+            None => "unknown".to_string()
+        };
+        println!("Name: {}\nVersion: {}\nEpisode: {} ({}).", name, version, episode, episode_source);
+
+        if let (Some(quest_number), Some(language)) = (quest.quest_number, quest.language) {
+            println!("Quest number: {}\nLanguage: {}", quest_number, language);
+        }
+
+        if let (Some(object_code_offset), Some(function_offset_table_offset), Some(bin_size)) =
+               (quest.object_code_offset, quest.function_offset_table_offset, quest.bin_size) {
+            println!(".bin file: {} bytes, object code at {:#x}, function offset table at {:#x}", bin_size,
+                     object_code_offset, function_offset_table_offset);
+        }
+
+        println!("Short description:\n\n{}\n\nLong description:\n\n{}", short_description, long_description);
+
+        for replaced in &quest.replaced_text {
+            println!("\nWarning: replaced {} invalid units in the {} (first at offset {:#x}).", replaced.count,
+                     replaced.field, replaced.offset);
+        }
+
+        if !quest.map_designations.is_empty() {
+            println!("\nMaps:");
+        }
+
+        for designation in &quest.map_designations {
+            println!("Area {} variant {}", designation.area, designation.variant);
+        }
+
+        if !quest.qst_headers.is_empty() {
+            println!("\nEmbedded files:");
+        }
+
+        for header in &quest.qst_headers {
+            println!("{} ({} bytes), {} quest {}", header.file_name, header.size, header.quest_type,
+                     header.quest_number);
+        }
+
+        for &(extension, stats) in &[("dat", quest.dat_compression), ("bin", quest.bin_compression)] {
+            if let Some(stats) = stats {
+                if stats.trailing_data {
+                    println!("\nWarning: the .{} file contains data after its end marker (compressed size {}).",
+                             extension, stats.compressed_size);
+                }
+            }
+        }
 
         println!("\nMonster counts:");
+        print_counts(monster_counts);
 
-        let max_count = monster_counts.values().fold(0, |acc, &count| { max(acc, count) }) as f64;
-        let number_width = max_count.log10().round() as usize + 1;
+        if !quest.unspawned_waves.is_empty() {
+            let mut unspawned_counts = BTreeMap::new();
 
-        for (monster_type, count) in monster_counts {
-            println!("{:>width$} {}", count, monster_type, width = number_width);
+            for wave in &quest.unspawned_waves {
+                for (&monster_type, &count) in &quest.wave_monster_counts[wave] {
+                    *unspawned_counts.entry(monster_type).or_insert(0) += count;
+                }
+            }
+
+            println!("\nWarning: no event spawns these monsters, they aren't counted above:");
+            print_counts(&unspawned_counts);
+        }
+
+        for (area, counts) in &quest.area_monster_counts {
+            println!("\nArea {}:", area);
+            print_counts(counts);
+        }
+
+        for (section, counts) in &quest.section_monster_counts {
+            println!("\n{}:", section);
+            print_counts(counts);
+        }
+
+        // Without events waves don't mean anything.
+        for (wave, counts) in quest.wave_monster_counts.iter().filter(|_| !quest.events.is_empty()) {
+            let note = if quest.unspawned_waves.contains(wave) { " (never spawns)" } else { "" };
+            println!("\n{}{}:", wave, note);
+            print_counts(counts);
+        }
+
+        if !quest.object_counts.is_empty() {
+            println!("\nObject counts:");
+            print_counts(&quest.object_counts);
         }
 
         println!("\n");
     }
 }
+
+fn print_events(quest: &Quest) {
+    for event in &quest.events {
+        println!("Event {} (area {}, section {}, wave {}, delay {}):", event.id, event.area, event.section, event.wave,
+                 event.delay);
+
+        for action in &event.actions {
+            println!("    {}", action);
+        }
+    }
+}
+
+// Prints a count per line, right aligned.
+fn print_counts<K: Display>(counts: &BTreeMap<K, u32>) {
+    let max_count = counts.values().fold(0, |acc, &count| { max(acc, count) }) as f64;
+    let number_width = max_count.log10().round() as usize + 1;
+
+    for (key, count) in counts {
+        println!("{:>width$} {}", count, key, width = number_width);
+    }
+}
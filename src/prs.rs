@@ -1,72 +1,208 @@
-use std::io::{self, Read, Write, Seek, SeekFrom};
+use std::fmt;
+use std::io::{self, Read, Write};
 
-pub fn decompress<S: Read, D: Read + Write + Seek>(src: &mut S, dst: &mut D) -> io::Result<()> {
+// How hard the compressor looks for earlier occurrences of the data it's about to write.
+// Fast only inspects the most recent candidates, Best searches the whole window and postpones a copy when the next
+// byte starts a better one.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Compression {
+    Fast,
+    Best
+}
+
+// Size of the history window back-references can point into.
+const WINDOW_SIZE: usize = 8192;
+// Largest offset the compressor uses. The offset 8192 itself is avoided because a long copy with that offset and an
+// extended size is indistinguishable from the end of file marker.
+const MAX_OFFSET: usize = WINDOW_SIZE - 1;
+const MAX_SHORT_COPY_OFFSET: usize = 256;
+const MAX_SHORT_COPY_SIZE: usize = 5;
+const MAX_LONG_COPY_SHORT_SIZE: usize = 9;
+const MAX_COPY_SIZE: usize = 256;
+// Number of candidates Compression::Fast inspects per position.
+const FAST_CHAIN_LENGTH: usize = 16;
+const NO_POSITION: usize = ::std::usize::MAX;
+
+// Output limit used when decompressing embedded quest files, quest files are much smaller than this.
+pub const DEFAULT_MAX_SIZE: usize = 16 * 1024 * 1024;
+
+#[derive(Debug)]
+pub enum DecompressError {
+    Io(io::Error),
+    // The compressed data ended before the end of file marker.
+    UnexpectedEnd { offset: u64 },
+    // A back-reference points before the start of the output, distance is the number of bytes it points back and
+    // available is the number of bytes written so far.
+    InvalidOffset { offset: u64, distance: usize, available: usize },
+    // The decompressed data would be larger than the configured maximum.
+    MaxSizeExceeded { offset: u64, max_size: usize }
+}
+
+impl fmt::Display for DecompressError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            DecompressError::Io(ref err) => write!(f, "{}", err),
+            DecompressError::UnexpectedEnd { offset } =>
+                write!(f, "the compressed data ends at offset {:#x} without an end marker", offset),
+            DecompressError::InvalidOffset { offset, distance, available } =>
+                write!(f, "the copy at offset {:#x} points {} bytes back, only {} bytes were decompressed", offset,
+                       distance, available),
+            DecompressError::MaxSizeExceeded { offset, max_size } =>
+                write!(f, "the data grows larger than {} bytes at offset {:#x}", max_size, offset)
+        }
+    }
+}
+
+impl From<io::Error> for DecompressError {
+    fn from(err: io::Error) -> DecompressError {
+        DecompressError::Io(err)
+    }
+}
+
+// Describes a completed decompression.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct DecompressStats {
+    // Number of compressed bytes up to and including the end of file marker.
+    pub compressed_size: u64,
+    pub decompressed_size: u64,
+    // Whether there was more data after the end of file marker.
+    pub trailing_data: bool
+}
+
+pub type DecompressResult<T> = ::std::result::Result<T, DecompressError>;
+
+// Decompresses PRS data from src into dst. Back-references are resolved from an internal window that holds the last
+// 8 KiB of output, so dst can be any writer.
+// Decompression stops with an error as soon as the output would grow larger than max_size. The offsets in errors are
+// positions in the compressed stream.
+// To find out whether there's trailing data, one byte past the end of file marker is read from src.
+pub fn decompress<S: Read, D: Write>(src: &mut S, dst: &mut D, max_size: usize) -> DecompressResult<DecompressStats> {
+    let mut window = Window {
+        dst: dst,
+        buf: vec![0; WINDOW_SIZE],
+        pos: 0,
+        written: 0
+    };
+    let compressed_size = decode(src, &mut window, max_size)?;
+    window.flush()?;
+
+    let trailing_data = loop {
+        match src.read(&mut [0]) {
+            Ok(size) => break size != 0,
+            Err(ref err) if err.kind() == io::ErrorKind::Interrupted => {},
+            Err(err) => return Err(DecompressError::Io(err))
+        }
+    };
+
+    return Ok(DecompressStats {
+        compressed_size: compressed_size,
+        decompressed_size: window.written as u64,
+        trailing_data: trailing_data
+    });
+}
+
+// Decompresses PRS data that's already in memory, back-references are resolved directly from the output.
+pub fn decompress_buf(data: &[u8], max_size: usize) -> DecompressResult<(Vec<u8>, DecompressStats)> {
+    let mut src = data;
+    let mut dst = Vec::with_capacity(::std::cmp::min(4 * data.len(), max_size));
+    let compressed_size = decode(&mut src, &mut dst, max_size)?;
+    let stats = DecompressStats {
+        compressed_size: compressed_size,
+        decompressed_size: dst.len() as u64,
+        trailing_data: !src.is_empty()
+    };
+    return Ok((dst, stats));
+}
+
+// Returns the number of compressed bytes read.
+fn decode<S: Read, O: Output>(src: &mut S, dst: &mut O, max_size: usize) -> DecompressResult<u64> {
     let mut cxt = Context {
         src: src,
-        dst: dst,
+        pos: 0,
         flags: 0,
         bit_pos: 0,
-        buf: [0; 256]
+        buf: [0; 2]
     };
 
     loop {
         if cxt.read_flag_bit()? == 1 {
             // Single byte copy.
-            cxt.copy_byte()?;
+            let offset = cxt.pos;
+            let byte = cxt.read_byte()?;
+
+            if dst.len() >= max_size {
+                return Err(DecompressError::MaxSizeExceeded { offset: offset, max_size: max_size });
+            }
+
+            dst.write_byte(byte)?;
         } else {
             // Multi byte copy.
-            let mut size: u16;
-            let mut offset: i16;
+            let mut size: usize;
+            let distance: usize;
+            let offset;
 
             if cxt.read_flag_bit()? == 0 {
                 // Short copy.
-                size = (cxt.read_flag_bit()? as u16) << 1;
-                size |= cxt.read_flag_bit()? as u16;
+                size = (cxt.read_flag_bit()? as usize) << 1;
+                size |= cxt.read_flag_bit()? as usize;
                 size += 2;
 
-                offset = cxt.read_byte()? as i16 - 256;
+                offset = cxt.pos;
+                distance = 256 - cxt.read_byte()? as usize;
             } else {
                 // Long copy or end of file.
-                offset = cxt.read_short()? as i16;
+                offset = cxt.pos;
+                let value = cxt.read_short()? as usize;
 
                 // Two zero bytes implies that this is the end of the file.
-                if offset == 0 {
-                    return Ok(());
+                if value == 0 {
+                    return Ok(cxt.pos);
                 }
 
                 // Do we need to read a size byte, or is it encoded in what we already have?
-                size = offset as u16 & 0b111;
-                offset = ((offset as u16) >> 3) as i16;
+                size = value & 0b111;
 
                 if size == 0 {
-                    size = cxt.read_byte()? as u16;
+                    size = cxt.read_byte()? as usize;
                     size += 1;
                 } else {
                     size += 2;
                 }
 
-                offset -= 8192;
+                distance = 8192 - (value >> 3);
+            }
+
+            if distance > dst.len() {
+                return Err(DecompressError::InvalidOffset {
+                    offset: offset,
+                    distance: distance,
+                    available: dst.len()
+                });
             }
 
-            cxt.offset_copy(offset, size)?;
+            if size > max_size - dst.len() {
+                return Err(DecompressError::MaxSizeExceeded { offset: offset, max_size: max_size });
+            }
+
+            dst.offset_copy(distance, size)?;
         }
     }
 }
 
-struct Context<'a, S: 'a + Read, D: 'a + Read + Write + Seek> {
+struct Context<'a, S: 'a + Read> {
     src: &'a mut S,
-    dst: &'a mut D,
+    // Number of compressed bytes read.
+    pos: u64,
     flags: u8,
     bit_pos: u8,
-    buf: [u8; 256]
+    buf: [u8; 2]
 }
 
-impl<'a, S: Read, D: Read + Write + Seek> Context<'a, S, D> {
-    fn read_flag_bit(&mut self) -> io::Result<u8> {
+impl<'a, S: Read> Context<'a, S> {
+    fn read_flag_bit(&mut self) -> DecompressResult<u8> {
         // Fetch a new flag byte when the previous byte has been processed.
         if self.bit_pos == 0 {
-            self.src.read_exact(&mut self.buf[0..1])?;
-            self.flags = self.buf[0];
+            self.flags = self.read_byte()?;
             self.bit_pos = 8;
         }
 
@@ -76,43 +212,435 @@ impl<'a, S: Read, D: Read + Write + Seek> Context<'a, S, D> {
         return Ok(rv);
     }
 
-    fn copy_byte(&mut self) -> io::Result<()> {
-        self.src.read_exact(&mut self.buf[0..1])?;
-        self.dst.write_all(&self.buf[0..1])?;
-        return Ok(());
-    }
-
-    fn read_byte(&mut self) -> io::Result<u8> {
-        self.src.read_exact(&mut self.buf[0..1])?;
+    fn read_byte(&mut self) -> DecompressResult<u8> {
+        self.read(1)?;
         return Ok(self.buf[0]);
     }
 
-    fn read_short(&mut self) -> io::Result<u16> {
+    fn read_short(&mut self) -> DecompressResult<u16> {
         // In little endian format.
-        self.src.read_exact(&mut self.buf[0..2])?;
+        self.read(2)?;
         return Ok(((self.buf[1] as u16) << 8) | (self.buf[0] as u16));
     }
 
-    fn offset_copy(&mut self, offset: i16, size: u16) -> io::Result<()> {
-        debug_assert!(-8192 <= offset && offset <= 0);
-        debug_assert!(1 <= size && size <= 256);
+    fn read(&mut self, size: usize) -> DecompressResult<()> {
+        match self.src.read_exact(&mut self.buf[0..size]) {
+            Ok(()) => {
+                self.pos += size as u64;
+                return Ok(());
+            },
+            Err(ref err) if err.kind() == io::ErrorKind::UnexpectedEof =>
+                return Err(DecompressError::UnexpectedEnd { offset: self.pos }),
+            Err(err) =>
+                return Err(DecompressError::Io(err))
+        }
+    }
+}
 
-        let offset = offset as i64;
-        let size = size as usize;
+// Destination of decompressed data.
+trait Output {
+    // Number of bytes written so far.
+    fn len(&self) -> usize;
 
-        // The size can be larger than -offset, in that case we copy -offset bytes size/-offset times.
-        let buf_size = ::std::cmp::min(-offset as usize, size);
-        let buf = &mut self.buf[0..buf_size];
-        self.dst.seek(SeekFrom::Current(offset))?;
-        self.dst.read_exact(buf)?;
-        self.dst.seek(SeekFrom::Current(-offset - buf_size as i64))?;
+    fn write_byte(&mut self, byte: u8) -> io::Result<()>;
 
-        for _ in 0..(size / buf_size) {
-            self.dst.write_all(buf)?;
+    // Copies size bytes starting distance bytes before the end of the output, the size can be larger than the
+    // distance. The distance is never larger than the output.
+    fn offset_copy(&mut self, distance: usize, size: usize) -> io::Result<()>;
+}
+
+impl Output for Vec<u8> {
+    fn len(&self) -> usize {
+        return Vec::len(self);
+    }
+
+    fn write_byte(&mut self, byte: u8) -> io::Result<()> {
+        self.push(byte);
+        return Ok(());
+    }
+
+    fn offset_copy(&mut self, distance: usize, size: usize) -> io::Result<()> {
+        let start = Vec::len(self) - distance;
+
+        for i in start..(start + size) {
+            let byte = self[i];
+            self.push(byte);
         }
 
-        self.dst.write_all(&buf[0..(size % buf_size)])?;
+        return Ok(());
+    }
+}
+
+// Keeps the last WINDOW_SIZE bytes of output around for back-references and writes them to dst every time the
+// window is full.
+struct Window<'a, D: 'a + Write> {
+    dst: &'a mut D,
+    buf: Vec<u8>,
+    // Position in buf of the next byte.
+    pos: usize,
+    // Total number of bytes written.
+    written: usize
+}
 
+impl<'a, D: Write> Window<'a, D> {
+    fn flush(&mut self) -> io::Result<()> {
+        self.dst.write_all(&self.buf[0..self.pos])?;
+        self.pos = 0;
         return Ok(());
     }
 }
+
+impl<'a, D: Write> Output for Window<'a, D> {
+    fn len(&self) -> usize {
+        return self.written;
+    }
+
+    fn write_byte(&mut self, byte: u8) -> io::Result<()> {
+        if self.pos == WINDOW_SIZE {
+            self.flush()?;
+        }
+
+        self.buf[self.pos] = byte;
+        self.pos += 1;
+        self.written += 1;
+        return Ok(());
+    }
+
+    fn offset_copy(&mut self, distance: usize, size: usize) -> io::Result<()> {
+        for _ in 0..size {
+            let byte = self.buf[(self.pos + WINDOW_SIZE - distance) % WINDOW_SIZE];
+            self.write_byte(byte)?;
+        }
+
+        return Ok(());
+    }
+}
+
+pub fn compress<S: Read, D: Write>(src: &mut S, dst: &mut D, compression: Compression) -> io::Result<()> {
+    let mut data = Vec::new();
+    src.read_to_end(&mut data)?;
+
+    let mut cxt = CompressContext {
+        dst: dst,
+        flags: 0,
+        bit_pos: 0,
+        buf: Vec::with_capacity(32)
+    };
+    let mut finder = MatchFinder::new(&data, compression);
+    let mut pos = 0;
+
+    while pos < data.len() {
+        finder.insert_until(pos);
+        let (mut offset, mut size) = finder.find(pos);
+
+        if compression == Compression::Best && size != 0 && pos + 1 < data.len() {
+            // Lazy matching, output a literal when the next position yields a better copy.
+            finder.insert_until(pos + 1);
+            let (next_offset, next_size) = finder.find(pos + 1);
+
+            if next_size != 0 && copy_savings(next_offset, next_size) - 9 > copy_savings(offset, size) {
+                cxt.write_literal(data[pos])?;
+                pos += 1;
+                offset = next_offset;
+                size = next_size;
+            }
+        }
+
+        if size == 0 {
+            cxt.write_literal(data[pos])?;
+            pos += 1;
+        } else {
+            cxt.write_copy(offset, size)?;
+            pos += size;
+        }
+    }
+
+    return cxt.finish();
+}
+
+// Number of bits saved by a copy compared to writing its bytes as literals, which cost 9 bits each.
+fn copy_savings(offset: usize, size: usize) -> isize {
+    let copy_bits = if offset <= MAX_SHORT_COPY_OFFSET && size <= MAX_SHORT_COPY_SIZE {
+        12
+    } else if size < 3 {
+        // Long copies can't encode less than 3 bytes without a size byte, which makes them larger than literals.
+        return 0;
+    } else if size <= MAX_LONG_COPY_SHORT_SIZE {
+        18
+    } else {
+        26
+    };
+
+    return 9 * size as isize - copy_bits;
+}
+
+struct CompressContext<'a, D: 'a + Write> {
+    dst: &'a mut D,
+    flags: u8,
+    bit_pos: u8,
+    // Data belonging to the current flag byte, it's written right after the flag byte is complete.
+    buf: Vec<u8>
+}
+
+impl<'a, D: Write> CompressContext<'a, D> {
+    fn write_flag_bit(&mut self, bit: u8) -> io::Result<()> {
+        // The decompressor only reads a new flag byte when it needs the next bit, so a full flag byte is written
+        // when the first bit of the following byte is produced.
+        if self.bit_pos == 8 {
+            self.flush()?;
+        }
+
+        self.flags |= bit << self.bit_pos;
+        self.bit_pos += 1;
+        return Ok(());
+    }
+
+    fn write_literal(&mut self, byte: u8) -> io::Result<()> {
+        self.write_flag_bit(1)?;
+        self.buf.push(byte);
+        return Ok(());
+    }
+
+    fn write_copy(&mut self, offset: usize, size: usize) -> io::Result<()> {
+        debug_assert!(1 <= offset && offset <= MAX_OFFSET);
+        debug_assert!(2 <= size && size <= MAX_COPY_SIZE);
+
+        if offset <= MAX_SHORT_COPY_OFFSET && size <= MAX_SHORT_COPY_SIZE {
+            // Short copy.
+            let size_bits = (size - 2) as u8;
+            self.write_flag_bit(0)?;
+            self.write_flag_bit(0)?;
+            self.write_flag_bit((size_bits >> 1) & 1)?;
+            self.write_flag_bit(size_bits & 1)?;
+            self.buf.push((MAX_SHORT_COPY_OFFSET - offset) as u8);
+        } else {
+            // Long copy, the size is stored in the lower 3 bits or in an extra byte if it doesn't fit.
+            debug_assert!(size >= 3);
+
+            self.write_flag_bit(0)?;
+            self.write_flag_bit(1)?;
+            let value = ((8192 - offset) << 3) as u16;
+
+            if size <= MAX_LONG_COPY_SHORT_SIZE {
+                self.write_short(value | (size - 2) as u16);
+            } else {
+                self.write_short(value);
+                self.buf.push((size - 1) as u8);
+            }
+        }
+
+        return Ok(());
+    }
+
+    fn write_short(&mut self, value: u16) {
+        // In little endian format.
+        self.buf.push(value as u8);
+        self.buf.push((value >> 8) as u8);
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        if self.bit_pos != 0 {
+            self.dst.write_all(&[self.flags])?;
+            self.dst.write_all(&self.buf)?;
+            self.flags = 0;
+            self.bit_pos = 0;
+            self.buf.clear();
+        }
+
+        return Ok(());
+    }
+
+    fn finish(&mut self) -> io::Result<()> {
+        // A long copy with two zero bytes marks the end of the file.
+        self.write_flag_bit(0)?;
+        self.write_flag_bit(1)?;
+        self.write_short(0);
+        return self.flush();
+    }
+}
+
+// Finds earlier occurrences of the data at a given position using hash chains keyed on the first two bytes.
+struct MatchFinder<'a> {
+    data: &'a [u8],
+    max_chain_length: usize,
+    // Most recent position of every two byte sequence.
+    head: Vec<usize>,
+    // Previous position with the same two byte sequence, for every position.
+    prev: Vec<usize>,
+    // Positions before this one have been inserted into the chains.
+    inserted: usize
+}
+
+impl<'a> MatchFinder<'a> {
+    fn new(data: &'a [u8], compression: Compression) -> MatchFinder<'a> {
+        return MatchFinder {
+            data: data,
+            max_chain_length: match compression {
+                Compression::Fast => FAST_CHAIN_LENGTH,
+                Compression::Best => MAX_OFFSET
+            },
+            head: vec![NO_POSITION; 0x10000],
+            prev: vec![NO_POSITION; data.len()],
+            inserted: 0
+        };
+    }
+
+    fn key(&self, pos: usize) -> usize {
+        return ((self.data[pos] as usize) << 8) | self.data[pos + 1] as usize;
+    }
+
+    // Makes the data before end available to later searches.
+    fn insert_until(&mut self, end: usize) {
+        let end = ::std::cmp::min(end, self.data.len().saturating_sub(1));
+
+        while self.inserted < end {
+            let pos = self.inserted;
+            let key = self.key(pos);
+            self.prev[pos] = self.head[key];
+            self.head[key] = pos;
+            self.inserted += 1;
+        }
+    }
+
+    // Returns the offset and size of the copy for pos that saves the most bits, or a size of 0 when no copy is smaller
+    // than writing literals.
+    fn find(&self, pos: usize) -> (usize, usize) {
+        if pos + 1 >= self.data.len() {
+            return (0, 0);
+        }
+
+        let max_size = ::std::cmp::min(MAX_COPY_SIZE, self.data.len() - pos);
+        let mut best_offset = 0;
+        let mut best_size = 0;
+        let mut best_savings = 0;
+        let mut candidate = self.head[self.key(pos)];
+        let mut chain_length = 0;
+
+        while candidate != NO_POSITION && chain_length < self.max_chain_length {
+            let offset = pos - candidate;
+
+            if offset > MAX_OFFSET {
+                break;
+            }
+
+            // Copies may overlap the data they produce, so comparing against the source data is fine.
+            let mut size = 2;
+
+            while size < max_size && self.data[candidate + size] == self.data[pos + size] {
+                size += 1;
+            }
+
+            let savings = copy_savings(offset, size);
+
+            if savings > best_savings {
+                best_offset = offset;
+                best_size = size;
+                best_savings = savings;
+
+                if size == max_size {
+                    break;
+                }
+            }
+
+            candidate = self.prev[candidate];
+            chain_length += 1;
+        }
+
+        return (best_offset, best_size);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use test_data;
+    use super::*;
+
+    fn noise(size: usize) -> Vec<u8> {
+        test_data::noise(size, 0x2545F491)
+    }
+
+    fn assert_round_trip(data: &[u8]) {
+        for &compression in &[Compression::Fast, Compression::Best] {
+            let mut compressed = Vec::new();
+            compress(&mut &data[..], &mut compressed, compression).unwrap();
+            let (decompressed, stats) = decompress_buf(&compressed, DEFAULT_MAX_SIZE).unwrap();
+
+            assert!(decompressed == data, "{:?} compression of {} bytes", compression, data.len());
+            assert_eq!(stats.compressed_size, compressed.len() as u64);
+            assert!(!stats.trailing_data);
+        }
+    }
+
+    #[test]
+    fn round_trip_empty_input() {
+        assert_round_trip(&[]);
+    }
+
+    #[test]
+    fn round_trip_input_shorter_than_a_copy() {
+        assert_round_trip(&[0x42]);
+        assert_round_trip(&[0x42, 0x42]);
+        assert_round_trip(&[0x42, 0x43]);
+    }
+
+    #[test]
+    fn round_trip_data_past_the_window() {
+        // A run much longer than the window and the largest copy.
+        assert_round_trip(&vec![0xAA; 3 * WINDOW_SIZE]);
+
+        // Repetitions just inside and just outside the window.
+        let block = noise(MAX_OFFSET);
+        assert_round_trip(&[&block[..], &block[..]].concat());
+        let block = noise(WINDOW_SIZE + 100);
+        assert_round_trip(&[&block[..], &block[..], &block[0..300]].concat());
+    }
+
+    #[test]
+    fn copies_round_trip_at_the_short_and_long_copy_limits() {
+        let history = noise(WINDOW_SIZE);
+        let offsets = [1, 2, MAX_SHORT_COPY_OFFSET, MAX_SHORT_COPY_OFFSET + 1, MAX_OFFSET];
+        let sizes = [2, 3, MAX_SHORT_COPY_SIZE, MAX_SHORT_COPY_SIZE + 1, MAX_LONG_COPY_SHORT_SIZE,
+                     MAX_LONG_COPY_SHORT_SIZE + 1, MAX_COPY_SIZE];
+
+        for &offset in &offsets {
+            for &size in &sizes {
+                // Long copies need at least 3 bytes.
+                if offset > MAX_SHORT_COPY_OFFSET && size < 3 {
+                    continue;
+                }
+
+                let mut compressed = Vec::new();
+                {
+                    let mut cxt = CompressContext { dst: &mut compressed, flags: 0, bit_pos: 0, buf: Vec::new() };
+
+                    for &byte in &history {
+                        cxt.write_literal(byte).unwrap();
+                    }
+
+                    cxt.write_copy(offset, size).unwrap();
+                    cxt.finish().unwrap();
+                }
+
+                let mut expected = history.clone();
+
+                for _ in 0..size {
+                    let byte = expected[expected.len() - offset];
+                    expected.push(byte);
+                }
+
+                let decompressed = decompress_buf(&compressed, DEFAULT_MAX_SIZE).unwrap().0;
+                assert!(decompressed == expected, "copy with offset {} and size {}", offset, size);
+            }
+        }
+    }
+
+    #[test]
+    fn copy_savings_at_the_size_limits() {
+        // Short copies cost 12 bits, long copies 18 bits and long copies with a size byte 26 bits.
+        assert_eq!(copy_savings(MAX_SHORT_COPY_OFFSET, MAX_SHORT_COPY_SIZE), 45 - 12);
+        assert_eq!(copy_savings(MAX_SHORT_COPY_OFFSET, MAX_SHORT_COPY_SIZE + 1), 54 - 18);
+        assert_eq!(copy_savings(MAX_SHORT_COPY_OFFSET + 1, 2), 0);
+        assert_eq!(copy_savings(MAX_OFFSET, MAX_LONG_COPY_SHORT_SIZE), 81 - 18);
+        assert_eq!(copy_savings(MAX_OFFSET, MAX_LONG_COPY_SHORT_SIZE + 1), 90 - 26);
+    }
+}
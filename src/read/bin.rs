@@ -1,24 +1,179 @@
 use std::io::{Read, Seek, SeekFrom};
+use byteorder::{ByteOrder, ReadBytesExt, LittleEndian};
 use read;
-use util::read_utf_16le_string;
+use script::{self, init};
+use types::{AreaVariant, Episode, Language, Version};
+use util::{TextDecoder, TextEncoding};
 
 // A .bin file contains meta information and the assembly code.
 pub struct BinFile {
+    // Offsets are relative to the start of the file.
+    pub object_code_offset: u32,
+    pub function_offset_table_offset: u32,
+    pub size: u32,
+    pub quest_number: u16,
+    pub language: Language,
     pub quest_name: String,
-    pub short_description: String
+    pub short_description: String,
+    pub long_description: String,
+    // Episode set by the initialization function of the script.
+    pub episode: Option<Episode>,
+    // Whether a Dreamcast/GameCube file uses features that only GameCube has.
+    pub gamecube: bool,
+    pub map_designations: Vec<AreaVariant>
 }
 
 // Low level read method for .bin files.
-pub fn read<T: Read + Seek>(data: &mut T) -> read::Result<BinFile> {
-    data.seek(SeekFrom::Current(0x18))?;
+// The header starts with the object code offset, function offset table offset and size of the file, followed by 4
+// unknown bytes. Dreamcast, GameCube and PC headers then contain the language, an unknown byte and the quest number.
+// Blue Burst headers contain the quest number, 2 unknown bytes, the language and 3 unknown bytes. GameCube files
+// store the episode in the high byte of the quest number, so only its low byte is read.
+// Dreamcast and GameCube files contain Shift-JIS or ISO-8859-1 strings depending on the language, PC and Blue Burst
+// files contain UTF-16 strings. Invalid units in the strings are handled by decoder.
+// Dreamcast and GameCube files share the format, GameCube files are recognized by an episode byte of 1, the
+// set_episode opcode or the opcodes starting with 0xF9. GameCube files that use none of these look like Dreamcast
+// files.
+pub fn read<T: Read + Seek>(data: &mut T, version: Version, decoder: &mut TextDecoder) -> read::Result<BinFile> {
+    let start_pos = data.seek(SeekFrom::Current(0))?;
+    let object_code_offset = data.read_u32::<LittleEndian>()?;
+    let function_offset_table_offset = data.read_u32::<LittleEndian>()?;
+    let size = data.read_u32::<LittleEndian>()?;
+    data.seek(SeekFrom::Current(4))?;
 
-    let mut name_buffer = [0; 64];
+    let (quest_number, language, gamecube_episode, char_size) = match version {
+        Version::DCGC => {
+            let language = data.read_u8()?;
+            data.seek(SeekFrom::Current(1))?;
+            let quest_number = data.read_u8()? as u16;
+            let episode = data.read_u8()?;
+            (quest_number, language, episode == 1, 1)
+        },
+        Version::PC => {
+            let language = data.read_u8()?;
+            data.seek(SeekFrom::Current(1))?;
+            let quest_number = data.read_u16::<LittleEndian>()?;
+            (quest_number, language, false, 2)
+        },
+        Version::BB => {
+            let quest_number = data.read_u16::<LittleEndian>()?;
+            data.seek(SeekFrom::Current(2))?;
+            let language = data.read_u8()?;
+            data.seek(SeekFrom::Current(3))?;
+            (quest_number, language, false, 2)
+        }
+    };
+
+    let strings_offset = data.seek(SeekFrom::Current(0))? - start_pos;
+    let mut name_buffer = vec![0; 32 * char_size];
     data.read_exact(&mut name_buffer)?;
-    let mut short_description_buffer = [0; 256];
+    let mut short_description_buffer = vec![0; 128 * char_size];
     data.read_exact(&mut short_description_buffer)?;
+    let mut long_description_buffer = vec![0; 288 * char_size];
+    data.read_exact(&mut long_description_buffer)?;
+
+    let language = Language::from(language);
+    let text_encoding = TextEncoding::for_quest(version, language);
+    let short_description_offset = strings_offset + name_buffer.len() as u64;
+    let long_description_offset = short_description_offset + short_description_buffer.len() as u64;
+    let quest_name = decoder.decode(&name_buffer, text_encoding, "quest name", strings_offset)?;
+    let short_description = decoder.decode(&short_description_buffer, text_encoding, "short description",
+                                           short_description_offset)?;
+    let long_description = decoder.decode(&long_description_buffer, text_encoding, "long description",
+                                          long_description_offset)?;
+
+    // The object code runs until the function offset table, which runs until the end of the file. Inconsistent
+    // offsets yield less code rather than an error, the header is still useful.
+    data.seek(SeekFrom::Start(start_pos + object_code_offset as u64))?;
+    let mut rest = Vec::new();
+    data.read_to_end(&mut rest)?;
+    let code_size = (function_offset_table_offset.saturating_sub(object_code_offset) as usize).min(rest.len());
+    let function_offsets: Vec<i32> = rest[code_size..]
+        .chunks(4)
+        .filter(|chunk| chunk.len() == 4)
+        .map(LittleEndian::read_i32)
+        .collect();
+    let init_function = init::read_function(&rest[0..code_size], &function_offsets, text_encoding);
+    let episode = init::episode(&init_function);
+    let gamecube = version == Version::DCGC
+        && (gamecube_episode || episode.is_some()
+            || uses_gamecube_opcodes(&rest[0..code_size], &function_offsets, text_encoding));
 
     return Ok(BinFile {
-        quest_name: read_utf_16le_string(&name_buffer[..])?,
-        short_description: read_utf_16le_string(&short_description_buffer[..])?
+        object_code_offset: object_code_offset,
+        function_offset_table_offset: function_offset_table_offset,
+        size: size,
+        quest_number: quest_number,
+        language: language,
+        quest_name: quest_name,
+        short_description: short_description,
+        long_description: long_description,
+        episode: episode,
+        gamecube: gamecube,
+        map_designations: init::map_designations(&init_function)
     });
 }
+
+// Returns true when a function uses an opcode starting with 0xF9, Dreamcast only has the opcodes up to 0xF8FF.
+fn uses_gamecube_opcodes(object_code: &[u8], function_offsets: &[i32], text_encoding: TextEncoding) -> bool {
+    return (0..function_offsets.len()).any(|label| {
+        script::read_function(object_code, function_offsets, label as u16, text_encoding)
+            .iter()
+            .any(|instruction| instruction.opcode.code >= 0xF900)
+    });
+}
+
+// Returns the language field of a .bin file without reading the rest of the header, None when data is too short.
+pub fn read_language(data: &[u8], version: Version) -> Option<Language> {
+    let offset = if version == Version::BB { 0x14 } else { 0x10 };
+    return data.get(offset).map(|&language| Language::from(language));
+}
+
+// Determines the version of a .bin file from its object code offset, the object code directly follows the header and
+// the header size differs per version. Returns None when data doesn't look like a .bin file.
+pub fn detect_version(data: &[u8]) -> Option<Version> {
+    if data.len() < 12 {
+        return None;
+    }
+
+    let object_code_offset = LittleEndian::read_u32(&data[0..4]);
+    let function_offset_table_offset = LittleEndian::read_u32(&data[4..8]);
+    let size = LittleEndian::read_u32(&data[8..12]);
+
+    if object_code_offset > function_offset_table_offset
+        || function_offset_table_offset > size
+        || size as usize > data.len() {
+        return None;
+    }
+
+    return match object_code_offset {
+        0x1D4 => Some(Version::DCGC),
+        0x394 => Some(Version::PC),
+        0x122C => Some(Version::BB),
+        _ => None
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+    use script::{self, asm};
+    use types::TextMode;
+    use util::TextDecoder;
+    use super::*;
+
+    fn is_gamecube(code: &str, episode_byte: u8) -> bool {
+        let text = format!(".version dcgc\n.label_count 1\n0:\n{}\nret\n", code);
+        let mut data = script::write(&asm::assemble(&text).unwrap()).unwrap();
+        data[0x13] = episode_byte;
+        let version = detect_version(&data).unwrap();
+        return read(&mut Cursor::new(data), version, &mut TextDecoder::new(TextMode::Strict)).unwrap().gamecube;
+    }
+
+    #[test]
+    fn gamecube_files_are_told_apart_from_dreamcast_files() {
+        assert!(!is_gamecube("nop", 0));
+        assert!(is_gamecube("nop", 1));
+        assert!(is_gamecube("set_episode 0", 0));
+        assert!(is_gamecube("dec2float r0, r1", 0));
+    }
+}
@@ -1,187 +1,302 @@
-use std::collections::BTreeMap;
+use std::collections::{BTreeMap, BTreeSet, HashSet};
 use std::io::{Read, Seek, SeekFrom};
-use byteorder::{ReadBytesExt, LittleEndian};
+use byteorder::{ByteOrder, ReadBytesExt, LittleEndian};
 use read;
-use types::{Episode, MonsterType};
+use types::{Episode, EpisodeSource, Event, EventAction, MonsterType, Npc, Object, ObjectType, Section, Wave};
 
 // A .dat file describes objects, enemies and more.
 pub struct DatFile {
     pub episode: Episode,
-    pub monster_counts: BTreeMap<MonsterType, u32>
+    pub episode_source: EpisodeSource,
+    // Monsters that spawn, monsters in waves that no event spawns aren't counted.
+    pub monster_counts: BTreeMap<MonsterType, u32>,
+    // The monster counts split up by area and by section.
+    pub area_monster_counts: BTreeMap<u32, BTreeMap<MonsterType, u32>>,
+    pub section_monster_counts: BTreeMap<Section, BTreeMap<MonsterType, u32>>,
+    // Every monster of the file by wave, including the unspawned waves.
+    pub wave_monster_counts: BTreeMap<Wave, BTreeMap<MonsterType, u32>>,
+    pub unspawned_waves: BTreeSet<Wave>,
+    pub object_counts: BTreeMap<ObjectType, u32>,
+    // Monsters, NPCs, objects and events in the order of the file.
+    pub npcs: Vec<Npc>,
+    pub objects: Vec<Object>,
+    pub events: Vec<Event>
 }
 
 // Low level read method for .dat files.
-pub fn read<T: Read + Seek>(data: &mut T) -> read::Result<DatFile> {
-    use types::MonsterType::*;
-
-    let monster_data = extract_monster_data(data)?;
-    let episode = detect_episode(&monster_data).unwrap_or(Episode::I);
+// Monster types depend on the episode, script_episode is the episode set by the .bin file's script if known. Otherwise
+// the episode is detected from the monsters, defaulting to episode I.
+pub fn read<T: Read + Seek>(data: &mut T, script_episode: Option<Episode>) -> read::Result<DatFile> {
+    let (objects, npcs, events) = read_tables(data)?;
+    let (episode, episode_source) = match (script_episode, detect_episode(&npcs)) {
+        (Some(episode), _) => (episode, EpisodeSource::Script),
+        (None, Some(episode)) => (episode, EpisodeSource::Monsters),
+        (None, None) => (Episode::I, EpisodeSource::Default)
+    };
 
     let mut dat_file = DatFile {
         episode: episode,
-        monster_counts: BTreeMap::new()
+        episode_source: episode_source,
+        monster_counts: BTreeMap::new(),
+        area_monster_counts: BTreeMap::new(),
+        section_monster_counts: BTreeMap::new(),
+        wave_monster_counts: BTreeMap::new(),
+        unspawned_waves: BTreeSet::new(),
+        object_counts: BTreeMap::new(),
+        npcs: Vec::new(),
+        objects: Vec::new(),
+        events: events
     };
 
-    for MonsterData { id, regular, skin, area } in monster_data {
-        let mut result = None;
-
-        result = match (id, skin % 3, episode) {
-            (0x044, 0, _) => Some(Booma),
-            (0x044, 1, _) => Some(Gobooma),
-            (0x044, 2, _) => Some(Gigobooma),
-
-            (0x063, 0, _) => Some(EvilShark),
-            (0x063, 1, _) => Some(PalShark),
-            (0x063, 2, _) => Some(GuilShark),
-
-            (0x0A6, 0, Episode::I) => Some(Dimenian),
-            (0x0A6, 0, Episode::II) => Some(Dimenian2),
-            (0x0A6, 1, Episode::I) => Some(LaDimenian),
-            (0x0A6, 1, Episode::II) => Some(LaDimenian2),
-            (0x0A6, 2, Episode::I) => Some(SoDimenian),
-            (0x0A6, 2, Episode::II) => Some(SoDimenian2),
-
-            (0x0D6, 0, _) => Some(Mericarol),
-            (0x0D6, 1, _) => Some(Mericus),
-            (0x0D6, 2, _) => Some(Merikle),
-
-            (0x115, 0, _) => Some(Boota),
-            (0x115, 1, _) => Some(ZeBoota),
-            (0x115, 2, _) => Some(BaBoota),
-            (0x117, 0, _) => Some(Goran),
-            (0x117, 1, _) => Some(PyroGoran),
-            (0x117, 2, _) => Some(GoranDetonator),
-
-            _ => result
-        };
-
-        result = match (id, skin % 2, episode) {
-            (0x040, 0, Episode::I) => Some(Hildebear),
-            (0x040, 0, Episode::II) => Some(Hildebear2),
-            (0x040, 1, Episode::I) => Some(Hildeblue),
-            (0x040, 1, Episode::II) => Some(Hildeblue2),
-            (0x041, 0, Episode::I) => Some(RagRappy),
-            (0x041, 0, Episode::II) => Some(RagRappy2),
-            (0x041, 0, Episode::IV) => Some(SandRappy),
-            (0x041, 1, Episode::I) => Some(AlRappy),
-            (0x041, 1, Episode::II) => Some(LoveRappy),
-            (0x041, 1, Episode::IV) => Some(DelRappy),
-
-            (0x061, 0, Episode::I) => Some(if area > 15 { DelLily } else { PoisonLily }),
-            (0x061, 0, Episode::II) => Some(if area > 15 { DelLily } else { PoisonLily2 }),
-            (0x061, 1, Episode::I) => Some(if area > 15 { DelLily } else { NarLily }),
-            (0x061, 1, Episode::II) => Some(if area > 15 { DelLily } else { NarLily2 }),
-
-            (0x080, 0, Episode::I) => Some(Dubchic),
-            (0x080, 0, Episode::II) => Some(Dubchic2),
-            (0x080, 1, Episode::I) => Some(Gilchic),
-            (0x080, 1, Episode::II) => Some(Gilchic2),
-
-            (0x0D4, 0, _) => Some(SinowBerill),
-            (0x0D4, 1, _) => Some(SinowSpigell),
-            (0x0D5, 0, _) => Some(Merillia),
-            (0x0D5, 1, _) => Some(Meriltas),
-            (0x0D7, 0, _) => Some(UlGibbon),
-            (0x0D7, 1, _) => Some(ZolGibbon),
-
-            (0x0DD, 0, _) => Some(Dolmolm),
-            (0x0DD, 1, _) => Some(Dolmdarl),
-            (0x0E0, 0, _) => Some(if area > 15 { Epsilon } else { SinowZoa }),
-            (0x0E0, 1, _) => Some(if area > 15 { Epsilon } else { SinowZele }),
-
-            (0x112, 0, _) => Some(MerissaA),
-            (0x112, 1, _) => Some(MerissaAA),
-            (0x114, 0, _) => Some(Zu),
-            (0x114, 1, _) => Some(Pazuzu),
-            (0x116, 0, _) => Some(Dorphon),
-            (0x116, 1, _) => Some(DorphonEclair),
-            (0x119, 0, _) => Some(if regular { SaintMillion } else { Kondrieu }),
-            (0x119, 1, _) => Some(if regular { Shambertin } else { Kondrieu }),
-
-            _ => result
-        };
-
-        result = match (id, episode) {
-            (0x042, Episode::I) => Some(Monest),
-            (0x042, Episode::II) => Some(Monest2),
-            (0x043, Episode::I) => Some(if regular { SavageWolf } else { BarbarousWolf }),
-            (0x043, Episode::II) => Some(if regular { SavageWolf2 } else { BarbarousWolf2 }),
-
-            (0x060, Episode::I) => Some(GrassAssassin),
-            (0x060, Episode::II) => Some(GrassAssassin2),
-            (0x062, _) => Some(NanoDragon),
-            (0x064, _) => Some(if regular { PofuillySlime } else { PouillySlime }),
-            (0x065, Episode::I) => Some(PanArms),
-            (0x065, Episode::II) => Some(PanArms2),
-
-            (0x081, Episode::I) => Some(Garanz),
-            (0x081, Episode::II) => Some(Garanz2),
-            (0x082, _) => Some(if regular { SinowBeat } else { SinowGold }),
-            (0x083, _) => Some(Canadine),
-            (0x084, _) => {
-                *dat_file.monster_counts.entry(Canadine).or_insert(0) += 8;
-                Some(Canane)
-            },
-            (0x085, Episode::I) => Some(Dubswitch),
-            (0x085, Episode::II) => Some(Dubswitch2),
-
-            (0x0A0, Episode::I) => Some(Delsaber),
-            (0x0A0, Episode::II) => Some(Delsaber2),
-            (0x0A1, Episode::I) => Some(ChaosSorcerer),
-            (0x0A1, Episode::II) => Some(ChaosSorcerer2),
-            (0x0A2, _) => Some(DarkGunner),
-            (0x0A4, _) => Some(ChaosBringer),
-            (0x0A5, Episode::I) => Some(DarkBelra),
-            (0x0A5, Episode::II) => Some(DarkBelra2),
-            (0x0A7, _) => Some(Bulclaw),
-            (0x0A8, _) => Some(Claw),
-
-            (0x0C0, Episode::I) => Some(Dragon),
-            (0x0C0, Episode::II) => Some(GalGryphon),
-            (0x0C1, _) => Some(DeRolLe),
-            (0x0C5, _) => Some(VolOpt),
-            (0x0C8, _) => Some(DarkFalz),
-            (0x0CA, _) => Some(OlgaFlow),
-            (0x0CB, _) => Some(BarbaRay),
-            (0x0CC, _) => Some(GolDragon),
-
-            (0x0D8, _) => Some(Gibbles),
-            (0x0D9, _) => Some(Gee),
-            (0x0DA, _) => Some(GiGue),
-
-            (0x0DB, _) => Some(Deldepth),
-            (0x0DC, _) => Some(Delbiter),
-            (0x0DE, _) => Some(Morfos),
-            (0x0DF, _) => Some(Recobox),
-            (0x0E1, _) => Some(IllGill),
-
-            (0x110, _) => Some(Astark),
-            (0x111, _) => Some(if regular { SatelliteLizard } else { Yowie }),
-            (0x113, _) => Some(Girtablulu),
-
-            _ => result
-        };
-
-        if let Some(monster_type) = result {
-            *dat_file.monster_counts.entry(monster_type).or_insert(0) += 1;
+    let spawned_waves = spawned_waves(&dat_file.events);
+    // Areas without events have nothing that tells which waves spawn, their monsters are assumed to spawn.
+    let event_areas: HashSet<u32> = dat_file.events.iter().map(|event| event.area).collect();
+
+    for npc in &npcs {
+        if let Some(monster_type) = monster_type(npc, episode) {
+            let wave = Wave { area: npc.area, section: npc.section, wave: npc.wave };
+            add_monster(dat_file.wave_monster_counts.entry(wave).or_insert_with(BTreeMap::new), monster_type);
+
+            if !event_areas.contains(&npc.area) || spawned_waves.contains(&wave) {
+                let section = Section { area: npc.area, section: npc.section };
+                add_monster(&mut dat_file.monster_counts, monster_type);
+                add_monster(dat_file.area_monster_counts.entry(npc.area).or_insert_with(BTreeMap::new), monster_type);
+                add_monster(dat_file.section_monster_counts.entry(section).or_insert_with(BTreeMap::new),
+                            monster_type);
+            } else {
+                dat_file.unspawned_waves.insert(wave);
+            }
         }
     }
 
+    for object in &objects {
+        *dat_file.object_counts.entry(object.object_type()).or_insert(0) += 1;
+    }
+
+    dat_file.npcs = npcs;
+    dat_file.objects = objects;
     return Ok(dat_file);
 }
 
-struct MonsterData {
-    id: u32,
-    regular: bool,
-    skin: u32,
-    area: u32
+// Returns the monster type of an NPC record, None for NPCs that aren't monsters.
+fn monster_type(npc: &Npc, episode: Episode) -> Option<MonsterType> {
+    use types::MonsterType::*;
+
+    let (id, regular, skin, area) = (npc.type_id, npc.regular(), npc.skin, npc.area);
+    let mut result = None;
+
+    result = match (id, skin % 3, episode) {
+        (0x044, 0, _) => Some(Booma),
+        (0x044, 1, _) => Some(Gobooma),
+        (0x044, 2, _) => Some(Gigobooma),
+
+        (0x063, 0, _) => Some(EvilShark),
+        (0x063, 1, _) => Some(PalShark),
+        (0x063, 2, _) => Some(GuilShark),
+
+        (0x0A6, 0, Episode::I) => Some(Dimenian),
+        (0x0A6, 0, Episode::II) => Some(Dimenian2),
+        (0x0A6, 1, Episode::I) => Some(LaDimenian),
+        (0x0A6, 1, Episode::II) => Some(LaDimenian2),
+        (0x0A6, 2, Episode::I) => Some(SoDimenian),
+        (0x0A6, 2, Episode::II) => Some(SoDimenian2),
+
+        (0x0D6, 0, _) => Some(Mericarol),
+        (0x0D6, 1, _) => Some(Mericus),
+        (0x0D6, 2, _) => Some(Merikle),
+
+        (0x115, 0, _) => Some(Boota),
+        (0x115, 1, _) => Some(ZeBoota),
+        (0x115, 2, _) => Some(BaBoota),
+        (0x117, 0, _) => Some(Goran),
+        (0x117, 1, _) => Some(PyroGoran),
+        (0x117, 2, _) => Some(GoranDetonator),
+
+        _ => result
+    };
+
+    result = match (id, skin % 2, episode) {
+        (0x040, 0, Episode::I) => Some(Hildebear),
+        (0x040, 0, Episode::II) => Some(Hildebear2),
+        (0x040, 1, Episode::I) => Some(Hildeblue),
+        (0x040, 1, Episode::II) => Some(Hildeblue2),
+        (0x041, 0, Episode::I) => Some(RagRappy),
+        (0x041, 0, Episode::II) => Some(RagRappy2),
+        (0x041, 0, Episode::IV) => Some(SandRappy),
+        (0x041, 1, Episode::I) => Some(AlRappy),
+        (0x041, 1, Episode::II) => Some(LoveRappy),
+        (0x041, 1, Episode::IV) => Some(DelRappy),
+
+        (0x061, 0, Episode::I) => Some(if area > 15 { DelLily } else { PoisonLily }),
+        (0x061, 0, Episode::II) => Some(if area > 15 { DelLily } else { PoisonLily2 }),
+        (0x061, 1, Episode::I) => Some(if area > 15 { DelLily } else { NarLily }),
+        (0x061, 1, Episode::II) => Some(if area > 15 { DelLily } else { NarLily2 }),
+
+        (0x080, 0, Episode::I) => Some(Dubchic),
+        (0x080, 0, Episode::II) => Some(Dubchic2),
+        (0x080, 1, Episode::I) => Some(Gilchic),
+        (0x080, 1, Episode::II) => Some(Gilchic2),
+
+        (0x0D4, 0, _) => Some(SinowBerill),
+        (0x0D4, 1, _) => Some(SinowSpigell),
+        (0x0D5, 0, _) => Some(Merillia),
+        (0x0D5, 1, _) => Some(Meriltas),
+        (0x0D7, 0, _) => Some(UlGibbon),
+        (0x0D7, 1, _) => Some(ZolGibbon),
+
+        (0x0DD, 0, _) => Some(Dolmolm),
+        (0x0DD, 1, _) => Some(Dolmdarl),
+        (0x0E0, 0, _) => Some(if area > 15 { Epsilon } else { SinowZoa }),
+        (0x0E0, 1, _) => Some(if area > 15 { Epsilon } else { SinowZele }),
+
+        (0x112, 0, _) => Some(MerissaA),
+        (0x112, 1, _) => Some(MerissaAA),
+        (0x114, 0, _) => Some(Zu),
+        (0x114, 1, _) => Some(Pazuzu),
+        (0x116, 0, _) => Some(Dorphon),
+        (0x116, 1, _) => Some(DorphonEclair),
+        (0x119, 0, _) => Some(if regular { SaintMillion } else { Kondrieu }),
+        (0x119, 1, _) => Some(if regular { Shambertin } else { Kondrieu }),
+
+        _ => result
+    };
+
+    result = match (id, episode) {
+        (0x042, Episode::I) => Some(Monest),
+        (0x042, Episode::II) => Some(Monest2),
+        (0x043, Episode::I) => Some(if regular { SavageWolf } else { BarbarousWolf }),
+        (0x043, Episode::II) => Some(if regular { SavageWolf2 } else { BarbarousWolf2 }),
+
+        (0x060, Episode::I) => Some(GrassAssassin),
+        (0x060, Episode::II) => Some(GrassAssassin2),
+        (0x062, _) => Some(NanoDragon),
+        (0x064, _) => Some(if regular { PofuillySlime } else { PouillySlime }),
+        (0x065, Episode::I) => Some(PanArms),
+        (0x065, Episode::II) => Some(PanArms2),
+
+        (0x081, Episode::I) => Some(Garanz),
+        (0x081, Episode::II) => Some(Garanz2),
+        (0x082, _) => Some(if regular { SinowBeat } else { SinowGold }),
+        (0x083, _) => Some(Canadine),
+        (0x084, _) => Some(Canane),
+        (0x085, Episode::I) => Some(Dubswitch),
+        (0x085, Episode::II) => Some(Dubswitch2),
+
+        (0x0A0, Episode::I) => Some(Delsaber),
+        (0x0A0, Episode::II) => Some(Delsaber2),
+        (0x0A1, Episode::I) => Some(ChaosSorcerer),
+        (0x0A1, Episode::II) => Some(ChaosSorcerer2),
+        (0x0A2, _) => Some(DarkGunner),
+        (0x0A4, _) => Some(ChaosBringer),
+        (0x0A5, Episode::I) => Some(DarkBelra),
+        (0x0A5, Episode::II) => Some(DarkBelra2),
+        (0x0A7, _) => Some(Bulclaw),
+        (0x0A8, _) => Some(Claw),
+
+        (0x0C0, Episode::I) => Some(Dragon),
+        (0x0C0, Episode::II) => Some(GalGryphon),
+        (0x0C1, _) => Some(DeRolLe),
+        (0x0C5, _) => Some(VolOpt),
+        (0x0C8, _) => Some(DarkFalz),
+        (0x0CA, _) => Some(OlgaFlow),
+        (0x0CB, _) => Some(BarbaRay),
+        (0x0CC, _) => Some(GolDragon),
+
+        (0x0D8, _) => Some(Gibbles),
+        (0x0D9, _) => Some(Gee),
+        (0x0DA, _) => Some(GiGue),
+
+        (0x0DB, _) => Some(Deldepth),
+        (0x0DC, _) => Some(Delbiter),
+        (0x0DE, _) => Some(Morfos),
+        (0x0DF, _) => Some(Recobox),
+        (0x0E1, _) => Some(IllGill),
+
+        (0x110, _) => Some(Astark),
+        (0x111, _) => Some(if regular { SatelliteLizard } else { Yowie }),
+        (0x113, _) => Some(Girtablulu),
+
+        _ => result
+    };
+
+    return result;
+}
+
+// A Canane comes with 8 Canadines.
+fn add_monster(counts: &mut BTreeMap<MonsterType, u32>, monster_type: MonsterType) {
+    *counts.entry(monster_type).or_insert(0) += 1;
+
+    if monster_type == MonsterType::Canane {
+        *counts.entry(MonsterType::Canadine).or_insert(0) += 8;
+    }
 }
 
-fn detect_episode(data: &Vec<MonsterData>) -> Option<Episode> {
-    for ref monster_data in data {
-        let area = monster_data.area;
+// Returns the waves that spawn. Events that no action in their area spawns or triggers are assumed to start when
+// the players enter their section. A started event spawns its wave, once the wave is cleared its actions spawn other
+// waves, which starts the events of those waves, and trigger other events. Events that can't be reached from those
+// first events never start, including events that only start each other in a cycle.
+fn spawned_waves(events: &[Event]) -> BTreeSet<Wave> {
+    let event_wave = |event: &Event| Wave { area: event.area, section: event.section, wave: event.wave };
+    let mut events_by_wave: BTreeMap<Wave, Vec<usize>> = BTreeMap::new();
+    let mut events_by_id: BTreeMap<(u32, u32), Vec<usize>> = BTreeMap::new();
+
+    for (index, event) in events.iter().enumerate() {
+        events_by_wave.entry(event_wave(event)).or_insert(Vec::new()).push(index);
+        events_by_id.entry((event.area, event.id)).or_insert(Vec::new()).push(index);
+    }
+
+    // The indices of the events each event starts.
+    let started_events: Vec<Vec<usize>> = events.iter()
+        .map(|event| {
+            event.actions.iter()
+                .filter_map(|action| match *action {
+                    EventAction::SpawnNpcs { section, wave } | EventAction::SpawnNpcsStop { section, wave } =>
+                        events_by_wave.get(&Wave { area: event.area, section: section, wave: wave }),
+                    EventAction::TriggerEvent { event_id } => events_by_id.get(&(event.area, event_id)),
+                    _ => None
+                })
+                .flat_map(|indices| indices.iter().cloned())
+                .collect()
+        })
+        .collect();
+    let mut started = vec![false; events.len()];
+    let mut has_starter = vec![false; events.len()];
+
+    for &index in started_events.iter().flat_map(|indices| indices.iter()) {
+        has_starter[index] = true;
+    }
 
-        match monster_data.id {
+    let mut queue: Vec<usize> = (0..events.len()).filter(|&index| !has_starter[index]).collect();
+    let mut spawned_waves = BTreeSet::new();
+
+    while let Some(index) = queue.pop() {
+        if started[index] {
+            continue;
+        }
+
+        started[index] = true;
+        let event = &events[index];
+        spawned_waves.insert(event_wave(event));
+        queue.extend(started_events[index].iter().cloned());
+
+        // Waves without an event of their own.
+        for action in &event.actions {
+            match *action {
+                EventAction::SpawnNpcs { section, wave } | EventAction::SpawnNpcsStop { section, wave } =>
+                    { spawned_waves.insert(Wave { area: event.area, section: section, wave: wave }); },
+                _ => {}
+            }
+        }
+    }
+
+    return spawned_waves;
+}
+
+fn detect_episode(npcs: &[Npc]) -> Option<Episode> {
+    for npc in npcs {
+        let area = npc.area;
+
+        match npc.type_id {
             0x44 | 0x62 ... 0x64 | 0x82 ... 0x84 | 0xA2 | 0xA4 | 0xA7 ... 0xA8 | 0xC1 | 0xC5 | 0xC8 =>
                 return Some(Episode::I),
             0xCA ... 0xE1 =>
@@ -208,9 +323,11 @@ fn detect_episode(data: &Vec<MonsterData>) -> Option<Episode> {
     return None;
 }
 
-fn extract_monster_data<T: Read + Seek>(data: &mut T) -> read::Result<Vec<MonsterData>> {
+fn read_tables<T: Read + Seek>(data: &mut T) -> read::Result<(Vec<Object>, Vec<Npc>, Vec<Event>)> {
     let mut pos = 0;
-    let mut vec = Vec::new();
+    let mut objects = Vec::new();
+    let mut npcs = Vec::new();
+    let mut events = Vec::new();
 
     while data.seek(SeekFrom::Start(pos)).is_ok() {
         let object_type = data.read_u32::<LittleEndian>()?;
@@ -218,30 +335,347 @@ fn extract_monster_data<T: Read + Seek>(data: &mut T) -> read::Result<Vec<Monste
         let area = data.read_u32::<LittleEndian>()?;
         let size = data.read_u32::<LittleEndian>()?;
 
-        if object_type == 2 {
-            let monster_count = size / 72;
-
-            for _ in 0..monster_count {
-                let id = data.read_u32::<LittleEndian>()?;
-                data.seek(SeekFrom::Current(44))?;
-                let regular = data.read_u32::<LittleEndian>()? & 0x800000 == 0;
-                data.seek(SeekFrom::Current(12))?;
-                let skin = data.read_u32::<LittleEndian>()?;
-                data.seek(SeekFrom::Current(4))?;
-
-                vec.push(MonsterData {
-                    id: id,
-                    regular: regular,
-                    skin: skin,
-                    area: area
-                });
+        if object_type == 1 {
+            for _ in 0..(size / 68) {
+                objects.push(read_object(data, area)?);
+            }
+        } else if object_type == 2 {
+            for _ in 0..(size / 72) {
+                npcs.push(read_npc(data, area)?);
             }
-        } else if object_type != 1 && object_type != 3 {
+        } else if object_type == 3 {
+            let mut table = Vec::new();
+            data.by_ref().take(size as u64).read_to_end(&mut table)?;
+            read_events(&table, area, &mut events);
+        } else {
             break;
         }
 
         pos += next_header as u64;
     }
 
-    return Ok(vec);
+    return Ok((objects, npcs, events));
+}
+
+// An event table starts with the offset of the actions, an unknown field, the number of events and another unknown
+// field. The 20 byte events follow, each with the offset of its actions relative to the start of the actions.
+// Events and actions that don't fit in the table are left out, so a damaged table doesn't stop the rest of the file
+// from being read.
+fn read_events(table: &[u8], area: u32, events: &mut Vec<Event>) {
+    if table.len() < 16 {
+        return;
+    }
+
+    let actions_offset = LittleEndian::read_u32(&table[0..]) as usize;
+    let event_count = LittleEndian::read_u32(&table[8..]) as usize;
+
+    for record in table[16..].chunks(20).take(event_count).filter(|record| record.len() == 20) {
+        let action_offset = actions_offset.saturating_add(LittleEndian::read_u32(&record[16..]) as usize);
+
+        events.push(Event {
+            id: LittleEndian::read_u32(&record[0..]),
+            flags: LittleEndian::read_u16(&record[4..]),
+            event_type: LittleEndian::read_u16(&record[6..]),
+            section: LittleEndian::read_u16(&record[8..]),
+            wave: LittleEndian::read_u16(&record[10..]),
+            delay: LittleEndian::read_u32(&record[12..]),
+            actions: table.get(action_offset..).map(read_event_actions).unwrap_or(Vec::new()),
+            area: area
+        });
+    }
+}
+
+// Actions are a one byte opcode followed by its arguments.
+fn read_event_actions(data: &[u8]) -> Vec<EventAction> {
+    let mut actions = Vec::new();
+    let mut pos = 0;
+
+    while pos < data.len() {
+        let opcode = data[pos];
+        let size = match opcode {
+            0x00 | 0x01 => 0,
+            0x0A | 0x0B => 2,
+            0x08 | 0x09 | 0x0C | 0x0D | 0x0E => 4,
+            _ => {
+                actions.push(EventAction::Unknown(opcode));
+                break;
+            }
+        };
+
+        if pos + 1 + size > data.len() {
+            break;
+        }
+
+        let args = &data[(pos + 1)..(pos + 1 + size)];
+        pos += 1 + size;
+
+        actions.push(match opcode {
+            0x00 => EventAction::Nop,
+            0x01 => break,
+            0x08 => EventAction::SpawnObjects {
+                section: LittleEndian::read_u16(args),
+                group: LittleEndian::read_u16(&args[2..])
+            },
+            0x09 => EventAction::SpawnNpcs {
+                section: LittleEndian::read_u16(args),
+                wave: LittleEndian::read_u16(&args[2..])
+            },
+            0x0A => EventAction::Unlock { switch_flag: LittleEndian::read_u16(args) },
+            0x0B => EventAction::Lock { switch_flag: LittleEndian::read_u16(args) },
+            0x0C => EventAction::TriggerEvent { event_id: LittleEndian::read_u32(args) },
+            0x0D => EventAction::SpawnNpcsStop {
+                section: LittleEndian::read_u16(args),
+                wave: LittleEndian::read_u16(&args[2..])
+            },
+            _ => EventAction::CallFunction { label: LittleEndian::read_u32(args) }
+        });
+    }
+
+    return actions;
+}
+
+fn read_object<T: Read>(data: &mut T, area: u32) -> read::Result<Object> {
+    let type_id = data.read_u16::<LittleEndian>()?;
+    let mut unknown = [0; 5];
+
+    for value in unknown.iter_mut() {
+        *value = data.read_u16::<LittleEndian>()?;
+    }
+
+    let section = data.read_u16::<LittleEndian>()?;
+    let unknown2 = data.read_u16::<LittleEndian>()?;
+    let position = read_f32_array(data)?;
+    let rotation = read_i32_array(data)?;
+    let float_params = read_f32_array(data)?;
+    let mut int_params = [0; 4];
+
+    for value in int_params.iter_mut() {
+        *value = data.read_u32::<LittleEndian>()?;
+    }
+
+    return Ok(Object {
+        type_id: type_id,
+        unknown: unknown,
+        section: section,
+        unknown2: unknown2,
+        position: position,
+        rotation: rotation,
+        float_params: float_params,
+        int_params: int_params,
+        area: area
+    });
+}
+
+fn read_npc<T: Read>(data: &mut T, area: u32) -> read::Result<Npc> {
+    let type_id = data.read_u16::<LittleEndian>()?;
+    let mut unknown = [0; 5];
+
+    for value in unknown.iter_mut() {
+        *value = data.read_u16::<LittleEndian>()?;
+    }
+
+    let section = data.read_u16::<LittleEndian>()?;
+    let wave = data.read_u16::<LittleEndian>()?;
+    let wave2 = data.read_u32::<LittleEndian>()?;
+    let position = read_f32_array(data)?;
+    let rotation = read_i32_array(data)?;
+    let scale = read_f32_array(data)?;
+    let param4 = data.read_f32::<LittleEndian>()?;
+    let script_label = data.read_f32::<LittleEndian>()? as u32;
+    let skin = data.read_u32::<LittleEndian>()?;
+    let param7 = data.read_u32::<LittleEndian>()?;
+
+    return Ok(Npc {
+        type_id: type_id,
+        unknown: unknown,
+        section: section,
+        wave: wave,
+        wave2: wave2,
+        position: position,
+        rotation: rotation,
+        scale: scale,
+        param4: param4,
+        script_label: script_label,
+        skin: skin,
+        param7: param7,
+        area: area
+    });
+}
+
+fn read_f32_array<T: Read>(data: &mut T) -> read::Result<[f32; 3]> {
+    return Ok([
+        data.read_f32::<LittleEndian>()?,
+        data.read_f32::<LittleEndian>()?,
+        data.read_f32::<LittleEndian>()?
+    ]);
+}
+
+fn read_i32_array<T: Read>(data: &mut T) -> read::Result<[i32; 3]> {
+    return Ok([
+        data.read_i32::<LittleEndian>()?,
+        data.read_i32::<LittleEndian>()?,
+        data.read_i32::<LittleEndian>()?
+    ]);
+}
+
+// Checks whether data consists of object, NPC and event tables, optionally followed by the end marker.
+pub fn is_dat(data: &[u8]) -> bool {
+    let mut pos = 0;
+
+    while pos + 16 <= data.len() {
+        let object_type = LittleEndian::read_u32(&data[pos..]);
+        let next_header = LittleEndian::read_u32(&data[(pos + 4)..]) as usize;
+        let size = LittleEndian::read_u32(&data[(pos + 12)..]) as usize;
+
+        if object_type == 0 && next_header == 0 {
+            return pos != 0;
+        }
+
+        if object_type < 1 || object_type > 3 || next_header < 16 || size > next_header - 16 {
+            return false;
+        }
+
+        pos += next_header;
+    }
+
+    return pos != 0 && pos == data.len();
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+    use byteorder::{ByteOrder, LittleEndian};
+    use types::{Episode, MonsterType};
+    use super::*;
+
+    fn table(table_type: u32, area: u32, body: &[u8]) -> Vec<u8> {
+        let mut data = vec![0; 16];
+        LittleEndian::write_u32(&mut data[0..], table_type);
+        LittleEndian::write_u32(&mut data[4..], 16 + body.len() as u32);
+        LittleEndian::write_u32(&mut data[8..], area);
+        LittleEndian::write_u32(&mut data[12..], body.len() as u32);
+        data.extend_from_slice(body);
+        return data;
+    }
+
+    // A Booma with scale 1.
+    fn booma(section: u16, wave: u16) -> Vec<u8> {
+        let mut record = vec![0; 72];
+        LittleEndian::write_u16(&mut record[0..], 0x44);
+        LittleEndian::write_u16(&mut record[0xC..], section);
+        LittleEndian::write_u16(&mut record[0xE..], wave);
+
+        for i in 0..3 {
+            LittleEndian::write_f32(&mut record[(0x2C + 4 * i)..], 1.0);
+        }
+
+        return record;
+    }
+
+    fn object(type_id: u16) -> Vec<u8> {
+        let mut record = vec![0; 68];
+        LittleEndian::write_u16(&mut record[0..], type_id);
+        return record;
+    }
+
+    // Events are (id, section, wave, action bytes), the end action is appended.
+    fn event_table(events: &[(u32, u16, u16, &[u8])]) -> Vec<u8> {
+        let mut records = Vec::new();
+        let mut actions = Vec::new();
+
+        for &(id, section, wave, event_actions) in events {
+            let mut record = vec![0; 20];
+            LittleEndian::write_u32(&mut record[0..], id);
+            LittleEndian::write_u16(&mut record[8..], section);
+            LittleEndian::write_u16(&mut record[10..], wave);
+            LittleEndian::write_u32(&mut record[16..], actions.len() as u32);
+            records.extend(record);
+            actions.extend_from_slice(event_actions);
+            actions.push(0x01);
+        }
+
+        let mut data = vec![0; 16];
+        LittleEndian::write_u32(&mut data[0..], 16 + records.len() as u32);
+        LittleEndian::write_u32(&mut data[8..], events.len() as u32);
+        data.extend(records);
+        data.extend(actions);
+        return data;
+    }
+
+    #[test]
+    fn objects_are_counted_by_type() {
+        let type_ids = [0, 2, 25, 27, 34, 34, 136, 26];
+        let objects: Vec<u8> = type_ids.iter().map(|&type_id| object(type_id)).collect::<Vec<_>>().concat();
+        let mut data = table(1, 0, &objects);
+        data.extend_from_slice(&[0; 16]);
+
+        let dat_file = read(&mut Cursor::new(data), Some(Episode::I)).unwrap();
+        let counts: Vec<(ObjectType, u32)> = dat_file.object_counts.into_iter().collect();
+
+        assert_eq!(counts, vec![
+            (ObjectType::PlayerSet, 1),
+            (ObjectType::Teleporter, 1),
+            (ObjectType::BossTeleporter, 1),
+            (ObjectType::QuestWarp, 1),
+            (ObjectType::TouchPlate, 2),
+            (ObjectType::RandomBox, 1),
+            (ObjectType::Unknown(26), 1)
+        ]);
+    }
+
+    #[test]
+    fn monsters_in_unreachable_waves_are_not_counted() {
+        let npcs: Vec<u8> = (1..6).map(|wave| booma(1, wave)).collect::<Vec<_>>().concat();
+        // Event 1 starts and spawns wave 2. Events 3 and 4 only trigger each other and nothing spawns wave 5.
+        let events = event_table(&[
+            (1, 1, 1, &[0x09, 1, 0, 2, 0]),
+            (3, 1, 3, &[0x0C, 4, 0, 0, 0]),
+            (4, 1, 4, &[0x0C, 3, 0, 0, 0])
+        ]);
+        let mut data = [table(2, 1, &npcs), table(3, 1, &events)].concat();
+        data.extend_from_slice(&[0; 16]);
+
+        let dat_file = read(&mut Cursor::new(data), Some(Episode::I)).unwrap();
+        let unspawned: Vec<u16> = dat_file.unspawned_waves.iter().map(|wave| wave.wave).collect();
+
+        assert_eq!(dat_file.monster_counts.get(&MonsterType::Booma), Some(&2));
+        assert_eq!(unspawned, vec![3, 4, 5]);
+    }
+
+    #[test]
+    fn events_started_only_by_a_cycle_never_spawn() {
+        let events = [
+            (1, 1, 1, &[0x0C, 2, 0, 0, 0][..]),
+            (2, 1, 2, &[0x0C, 1, 0, 0, 0, 0x09, 2, 0, 7, 0][..]),
+            (3, 2, 7, &[][..]),
+            (4, 3, 1, &[][..])
+        ];
+        let data = event_table(&events);
+        let mut parsed = Vec::new();
+        read_events(&data, 1, &mut parsed);
+
+        let waves: Vec<(u16, u16)> = spawned_waves(&parsed).iter().map(|wave| (wave.section, wave.wave)).collect();
+        assert_eq!(waves, vec![(3, 1)]);
+    }
+
+    #[test]
+    fn event_actions_are_decoded_until_the_end_action() {
+        let actions = read_event_actions(&[
+            0x00,
+            0x08, 1, 0, 2, 0,
+            0x0A, 3, 0,
+            0x0E, 100, 0, 0, 0,
+            0x0C, 4, 0, 0, 0,
+            0x01,
+            0x0B, 5, 0
+        ]);
+
+        assert_eq!(actions, vec![
+            EventAction::Nop,
+            EventAction::SpawnObjects { section: 1, group: 2 },
+            EventAction::Unlock { switch_flag: 3 },
+            EventAction::CallFunction { label: 100 },
+            EventAction::TriggerEvent { event_id: 4 }
+        ]);
+    }
 }
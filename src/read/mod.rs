@@ -1,29 +1,56 @@
 pub mod quest;
-mod qst;
-mod dat;
-mod bin;
+pub mod qst;
+pub mod dat;
+pub mod bin;
 
-use std::borrow::Cow;
+use std::fmt;
 use std::io;
 use std::result;
+use prs::DecompressError;
 
 pub type Result<T> = result::Result<T, ReadError>;
 
 #[derive(Debug)]
 pub enum ReadError {
     Io(io::Error),
-    Encoding(Cow<'static, str>),
+    // A string field contains an invalid unit at offset, which is relative to the (decompressed) file the string is
+    // in. Only returned in strict mode.
+    Encoding { field: &'static str, offset: u64 },
+    Decompression(DecompressError),
+    // A chunk of an embedded .qst file was never found.
+    MissingChunk { file_name: String, index: u32 },
+    // A chunk of an embedded .qst file was found more than once.
+    DuplicateChunk { file_name: String, index: u32 },
+    // Two chunks of an embedded .qst file claim the same part of the file but contain different data.
+    OverlappingChunk { file_name: String, index: u32 },
     InvalidData
 }
 
+impl fmt::Display for ReadError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            ReadError::Io(ref err) => write!(f, "{}", err),
+            ReadError::Encoding { field, offset } => write!(f, "invalid text in the {} at offset {:#x}", field, offset),
+            ReadError::Decompression(ref err) => write!(f, "invalid PRS data, {}", err),
+            ReadError::MissingChunk { ref file_name, index } =>
+                write!(f, "chunk {} of {} is missing", index, file_name),
+            ReadError::DuplicateChunk { ref file_name, index } =>
+                write!(f, "chunk {} of {} appears more than once", index, file_name),
+            ReadError::OverlappingChunk { ref file_name, index } =>
+                write!(f, "chunk {} of {} appears more than once with different data", index, file_name),
+            ReadError::InvalidData => write!(f, "invalid data")
+        }
+    }
+}
+
 impl From<io::Error> for ReadError {
     fn from(err: io::Error) -> ReadError {
         ReadError::Io(err)
     }
 }
 
-impl From<Cow<'static, str>> for ReadError {
-    fn from(err: Cow<'static, str>) -> ReadError {
-        ReadError::Encoding(err)
+impl From<DecompressError> for ReadError {
+    fn from(err: DecompressError) -> ReadError {
+        ReadError::Decompression(err)
     }
 }
@@ -1,100 +1,372 @@
 use std::io::{self, Cursor, Read, Seek, SeekFrom};
-use byteorder::{ReadBytesExt, LittleEndian};
-use prs;
+use byteorder::{ByteOrder, ReadBytesExt, LittleEndian};
+use prs::{self, DecompressStats};
 use read::{self, ReadError};
 use read::dat::{self, DatFile};
 use read::bin::{self, BinFile};
-use util::read_ascii_string;
+use types::{Language, QstHeader, QuestType, Version};
+use util::{TextDecoder, TextEncoding};
 
-// A .qst file contains two headers describing the embedded files, a .dat and a .bin file in that order.
+// A .qst file contains headers describing the embedded files, followed by the .dat and .bin files in chunks.
 pub struct QstFile {
+    pub version: Version,
+    pub headers: Vec<QstHeader>,
     pub dat: DatFile,
-    pub bin: BinFile
+    pub bin: BinFile,
+    pub dat_compression: DecompressStats,
+    pub bin_compression: DecompressStats
 }
 
-// Low level read method for .qst files.
-pub fn read<T: Read + Seek>(data: &mut T) -> read::Result<QstFile> {
-    // Read headers.
-    // A .qst file contains two 88-byte headers that describe the embedded .dat and .bin files.
-    let mut dat_size = 0;
-    let mut bin_size = 0;
+// Every version uses the same packets, but the packet header layout and size depend on the version.
+// Dreamcast and GameCube packets start with a 1 byte command, a 1 byte flags field and a 2 byte size, PC packets have
+// the same fields with the size first. Blue Burst packets have an 8 byte header, a 2 byte size, 2 byte command and 4
+// byte flags field.
+// The flags field of a header packet contains the quest number.
+const ONLINE_QUEST_HEADER: u16 = 0x44;
+const DOWNLOAD_QUEST_HEADER: u16 = 0xA6;
+const DC_GC_PC_HEADER_SIZE: u16 = 0x3C;
+const BB_HEADER_SIZE: u16 = 0x58;
 
-    for i in 0..2 {
-        data.seek(SeekFrom::Current(44))?;
-        let mut buffer = [0; 16];
-        data.read_exact(&mut buffer)?;
-        let file_name = read_ascii_string(&buffer)?;
-        let size = data.read_u32::<LittleEndian>()?;
-        data.seek(SeekFrom::Current(24))?;
-
-        if i == 0 && file_name.ends_with(".dat") {
-            dat_size = size;
-        } else if i == 1 && file_name.ends_with(".bin") {
-            bin_size = size;
+// Low level read method for .qst files. Invalid units in the strings of the headers and the .bin file are handled by
+// decoder.
+pub fn read<T: Read + Seek>(data: &mut T, decoder: &mut TextDecoder) -> read::Result<QstFile> {
+    // Extract the embedded files and decompress them.
+    // There's always a .dat and a .bin file, in any order.
+    let (version, raw_headers, file_data) = extract_raw(data, decoder)?;
+    let dat_index = raw_headers.iter().position(|h| h.file_name.ends_with(".dat")).ok_or(ReadError::InvalidData)?;
+    let bin_index = raw_headers.iter().position(|h| h.file_name.ends_with(".bin")).ok_or(ReadError::InvalidData)?;
+    let (decompressed_dat, dat_compression) = prs::decompress_buf(&file_data[dat_index], prs::DEFAULT_MAX_SIZE)?;
+    let (decompressed_bin, bin_compression) = prs::decompress_buf(&file_data[bin_index], prs::DEFAULT_MAX_SIZE)?;
+
+    // Read the embedded files, the .dat file needs the episode from the .bin file and the quest names in the headers
+    // need its language.
+    let bin = bin::read(&mut Cursor::new(decompressed_bin), version, decoder)?;
+    let dat = dat::read(&mut Cursor::new(decompressed_dat), bin.episode)?;
+    let headers = decode_quest_names(raw_headers, bin.language, decoder)?;
+
+    return Ok(QstFile {
+        version: version,
+        headers: headers,
+        dat: dat,
+        bin: bin,
+        dat_compression: dat_compression,
+        bin_compression: bin_compression
+    });
+}
+
+// Reads the headers and reassembles the embedded files without decompressing them.
+// The data of the embedded files is returned in the same order as their headers. Only the .bin file is decompressed,
+// to find the language of the quest names in the headers.
+pub fn extract<T: Read + Seek>(data: &mut T, decoder: &mut TextDecoder)
+                               -> read::Result<(Version, Vec<QstHeader>, Vec<Vec<u8>>)> {
+    let (version, raw_headers, file_data) = extract_raw(data, decoder)?;
+    // Without a readable .bin file the quest names are read as ISO-8859-1.
+    let language = raw_headers.iter()
+        .position(|h| h.file_name.ends_with(".bin"))
+        .and_then(|bin_index| prs::decompress_buf(&file_data[bin_index], prs::DEFAULT_MAX_SIZE).ok())
+        .and_then(|(bin_data, _)| bin::read_language(&bin_data, version))
+        .unwrap_or(Language::English);
+    let headers = decode_quest_names(raw_headers, language, decoder)?;
+    return Ok((version, headers, file_data));
+}
+
+// Checks whether data starts with a .qst header, leaves the position of data unchanged.
+pub fn is_qst<T: Read + Seek>(data: &mut T) -> read::Result<bool> {
+    return Ok(detect_version(data)?.is_some());
+}
+
+// Determines the version by checking which packet header layout yields a valid first header, returns None if none
+// does. Leaves the position of data unchanged.
+fn detect_version<T: Read + Seek>(data: &mut T) -> read::Result<Option<Version>> {
+    let start_pos = data.seek(SeekFrom::Current(0))?;
+    let mut buffer = [0; 4];
+    let result = data.read_exact(&mut buffer);
+    data.seek(SeekFrom::Start(start_pos))?;
+
+    match result {
+        Ok(()) => {},
+        Err(ref err) if err.kind() == io::ErrorKind::UnexpectedEof => return Ok(None),
+        Err(err) => return Err(ReadError::Io(err))
+    }
+
+    let first_short = LittleEndian::read_u16(&buffer[0..2]);
+    let second_short = LittleEndian::read_u16(&buffer[2..4]);
+
+    if first_short == BB_HEADER_SIZE && is_header_command(second_short) {
+        return Ok(Some(Version::BB));
+    } else if first_short == DC_GC_PC_HEADER_SIZE && is_header_command(buffer[2] as u16) {
+        return Ok(Some(Version::PC));
+    } else if second_short == DC_GC_PC_HEADER_SIZE && is_header_command(buffer[0] as u16) {
+        return Ok(Some(Version::DCGC));
+    } else {
+        return Ok(None);
+    }
+}
+
+fn is_header_command(command: u16) -> bool {
+    return command == ONLINE_QUEST_HEADER || command == DOWNLOAD_QUEST_HEADER;
+}
+
+// Returns the command, flags and size fields of the packet header at the current position.
+fn read_packet_header<T: Read>(data: &mut T, version: Version) -> read::Result<(u16, u32, u16)> {
+    match version {
+        Version::DCGC => {
+            let command = data.read_u8()? as u16;
+            let flags = data.read_u8()? as u32;
+            let size = data.read_u16::<LittleEndian>()?;
+            return Ok((command, flags, size));
+        },
+        Version::PC => {
+            let size = data.read_u16::<LittleEndian>()?;
+            let command = data.read_u8()? as u16;
+            let flags = data.read_u8()? as u32;
+            return Ok((command, flags, size));
+        },
+        Version::BB => {
+            let size = data.read_u16::<LittleEndian>()?;
+            let command = data.read_u16::<LittleEndian>()?;
+            let flags = data.read_u32::<LittleEndian>()?;
+            return Ok((command, flags, size));
         }
     }
+}
 
-    if dat_size == 0 || bin_size == 0 {
-        return Err(ReadError::InvalidData);
+// A header whose quest name hasn't been decoded yet, its encoding depends on the language of the .bin file.
+struct RawHeader {
+    quest_type: QuestType,
+    quest_number: u16,
+    quest_name: Vec<u8>,
+    quest_name_offset: u64,
+    file_name: String,
+    size: u32
+}
+
+fn extract_raw<T: Read + Seek>(data: &mut T, decoder: &mut TextDecoder)
+                               -> read::Result<(Version, Vec<RawHeader>, Vec<Vec<u8>>)> {
+    let version = detect_version(data)?.ok_or(ReadError::InvalidData)?;
+
+    // A .qst file starts with a header for every embedded file.
+    let mut headers = Vec::new();
+
+    while let Some(header) = read_header(data, version, decoder)? {
+        headers.push(header);
     }
 
-    // Extract the embedded files and decompress them.
-    let (dat_file_data, bin_file_data) = extract_file_data(data, dat_size as usize, bin_size as usize)?;
-    let decompressed_dat = decompress(&dat_file_data[..])?;
-    let decompressed_bin = decompress(&bin_file_data[..])?;
+    let file_data = extract_file_data(data, version, &headers, decoder)?;
+    return Ok((version, headers, file_data));
+}
 
-    // Read the embedded files.
-    return Ok(QstFile {
-        dat: dat::read(&mut Cursor::new(decompressed_dat))?,
-        bin: bin::read(&mut Cursor::new(decompressed_bin))?
-    });
+fn decode_quest_names(raw_headers: Vec<RawHeader>, language: Language, decoder: &mut TextDecoder)
+                      -> read::Result<Vec<QstHeader>> {
+    let text_encoding = TextEncoding::for_language(language);
+    let mut headers = Vec::with_capacity(raw_headers.len());
+
+    for raw_header in raw_headers {
+        headers.push(QstHeader {
+            quest_type: raw_header.quest_type,
+            quest_number: raw_header.quest_number,
+            quest_name: decoder.decode(&raw_header.quest_name, text_encoding, "header quest name",
+                                       raw_header.quest_name_offset)?,
+            file_name: raw_header.file_name,
+            size: raw_header.size
+        });
+    }
+
+    return Ok(headers);
+}
+
+// Reads a header packet, returns None and leaves the position unchanged when the next packet isn't a header.
+fn read_header<T: Read + Seek>(data: &mut T, version: Version, decoder: &mut TextDecoder)
+                              -> read::Result<Option<RawHeader>> {
+    let start_pos = data.seek(SeekFrom::Current(0))?;
+    let (command, flags, _) = read_packet_header(data, version)?;
+
+    if !is_header_command(command) {
+        data.seek(SeekFrom::Start(start_pos))?;
+        return Ok(None);
+    }
+
+    let mut quest_name_buffer = [0; 32];
+    let mut file_name_buffer = [0; 16];
+    let quest_name_offset;
+    let file_name_offset;
+    let size;
+
+    match version {
+        Version::DCGC | Version::PC => {
+            // 32 byte quest name and 4 unknown bytes.
+            quest_name_offset = data.seek(SeekFrom::Current(0))?;
+            data.read_exact(&mut quest_name_buffer)?;
+            data.seek(SeekFrom::Current(4))?;
+            file_name_offset = data.seek(SeekFrom::Current(0))?;
+            data.read_exact(&mut file_name_buffer)?;
+            size = data.read_u32::<LittleEndian>()?;
+        },
+        Version::BB => {
+            // 36 unknown bytes and a 24 byte quest name at the end.
+            data.seek(SeekFrom::Current(36))?;
+            file_name_offset = data.seek(SeekFrom::Current(0))?;
+            data.read_exact(&mut file_name_buffer)?;
+            size = data.read_u32::<LittleEndian>()?;
+            quest_name_offset = data.seek(SeekFrom::Current(0))?;
+            data.read_exact(&mut quest_name_buffer[0..24])?;
+        }
+    }
+
+    return Ok(Some(RawHeader {
+        quest_type: if command == ONLINE_QUEST_HEADER { QuestType::Online } else { QuestType::Download },
+        quest_number: flags as u16,
+        quest_name: quest_name_buffer.to_vec(),
+        quest_name_offset: quest_name_offset,
+        file_name: decoder.decode(&file_name_buffer, TextEncoding::Ascii, "header file name", file_name_offset)?,
+        size: size
+    }));
 }
 
-fn extract_file_data<T: Read + Seek>(data: &mut T, dat_size: usize, bin_size: usize) -> read::Result<(Vec<u8>, Vec<u8>)> {
-    // .dat and .bin files are interleaved in 1056 byte blocks.
-    // Each block has a 24 byte header, 1024 data segment and an 8 byte trailer.
-    let mut dat_data = vec![0; dat_size];
-    let mut bin_data = vec![0; bin_size];
-    let mut dat_read = 0;
-    let mut bin_read = 0;
+// Reassembles the embedded files described by headers.
+fn extract_file_data<T: Read + Seek>(data: &mut T, version: Version, headers: &[RawHeader], decoder: &TextDecoder)
+                                     -> read::Result<Vec<Vec<u8>>> {
+    // The embedded files are interleaved in chunks.
+    // Each chunk has a packet header, a 16 byte file name, a 1024 byte data segment and the size of the data. Blue
+    // Burst chunks are padded to 1056 bytes, the others are 1048 bytes.
+    // The flags field of the packet header contains the index of the chunk within its file. Chunks usually appear in
+    // order, but they're placed by index so reordered files can be read too. All chunks up to the end of the data are
+    // read, so duplicates are detected wherever they are.
+    let padding = match version {
+        Version::DCGC | Version::PC => 0,
+        Version::BB => 4
+    };
+
+    // Every byte of a file takes up more than a byte of the chunks that follow, so sizes that don't fit in the rest of
+    // the data are invalid. This keeps crafted headers from allocating huge buffers.
+    let start_pos = data.seek(SeekFrom::Current(0))?;
+    let remaining = data.seek(SeekFrom::End(0))? - start_pos;
+    data.seek(SeekFrom::Start(start_pos))?;
+
+    if headers.iter().map(|h| h.size as u64).sum::<u64>() > remaining {
+        return Err(ReadError::InvalidData);
+    }
+
+    let mut files: Vec<Vec<u8>> = headers.iter().map(|h| vec![0; h.size as usize]).collect();
+    // The size of every chunk that has been read so far, per file.
+    let mut chunk_sizes: Vec<Vec<Option<usize>>> = headers.iter()
+        .map(|h| vec![None; (h.size as usize + 1023) / 1024])
+        .collect();
+    // Chunk file names repeat the header file names, so replacements in them aren't reported again.
+    let mut file_name_decoder = TextDecoder::new(decoder.mode);
 
-    while dat_read < dat_size || bin_read < bin_size {
-        let start_pos = data.seek(SeekFrom::Current(8))? - 8;
+    loop {
+        let index = match read_packet_header(data, version) {
+            Ok((_, flags, _)) => flags as usize,
+            Err(ReadError::Io(ref err)) if err.kind() == io::ErrorKind::UnexpectedEof => break,
+            Err(err) => return Err(err)
+        };
         let mut buffer = [0; 16];
+        let file_name_offset = data.seek(SeekFrom::Current(0))?;
         data.read_exact(&mut buffer)?;
-        let file_name = read_ascii_string(&buffer)?;
-        data.seek(SeekFrom::Current(1024))?;
+        let file_name = file_name_decoder.decode(&buffer, TextEncoding::Ascii, "chunk file name", file_name_offset)?;
+        file_name_decoder.replaced.clear();
+        let mut chunk_data = [0; 1024];
+        data.read_exact(&mut chunk_data)?;
         let size = data.read_u32::<LittleEndian>()? as usize;
+        data.seek(SeekFrom::Current(padding))?;
 
-        if size <= 1024 {
-            if file_name.ends_with(".dat") {
-                data.seek(SeekFrom::Current(-1028))?;
-                data.read_exact(&mut dat_data[dat_read..(dat_read + size) as usize])?;
-                dat_read += size;
-                data.seek(SeekFrom::Current(1028 - size as i64))?;
-            } else if file_name.ends_with(".bin") {
-                data.seek(SeekFrom::Current(-1028))?;
-                data.read_exact(&mut bin_data[bin_read..(bin_read + size) as usize])?;
-                bin_read += size;
-                data.seek(SeekFrom::Current(1028 - size as i64))?;
-            }
-        } else {
+        let file_index = match headers.iter().position(|h| h.file_name == file_name) {
+            Some(file_index) => file_index,
+            None => continue
+        };
+        let file_data = &mut files[file_index];
+        let start = index * 1024;
+
+        if size > 1024 || index >= chunk_sizes[file_index].len() || start + size > file_data.len() {
             return Err(ReadError::InvalidData);
         }
 
-        let end_pos = data.seek(SeekFrom::Current(4))?;
-        debug_assert!(start_pos == end_pos - 1056);
+        if let Some(previous_size) = chunk_sizes[file_index][index] {
+            let chunk_error = if previous_size == size && file_data[start..(start + size)] == chunk_data[0..size] {
+                ReadError::DuplicateChunk { file_name: file_name, index: index as u32 }
+            } else {
+                ReadError::OverlappingChunk { file_name: file_name, index: index as u32 }
+            };
+            return Err(chunk_error);
+        }
+
+        file_data[start..(start + size)].copy_from_slice(&chunk_data[0..size]);
+        chunk_sizes[file_index][index] = Some(size);
     }
 
-    if dat_read != dat_size || bin_read != bin_size {
-        return Err(ReadError::InvalidData);
+    for (header, sizes) in headers.iter().zip(chunk_sizes.iter()) {
+        for (index, size) in sizes.iter().enumerate() {
+            match *size {
+                None =>
+                    return Err(ReadError::MissingChunk { file_name: header.file_name.clone(), index: index as u32 }),
+                // Only the last chunk can be smaller than 1024 bytes, anything else leaves a gap in the file.
+                Some(size) if size != 1024 && index != sizes.len() - 1 =>
+                    return Err(ReadError::InvalidData),
+                _ => {}
+            }
+        }
     }
 
-    return Ok((dat_data, bin_data));
+    return Ok(files);
 }
 
-fn decompress(data: &[u8]) -> io::Result<Vec<u8>> {
-    let mut decompressed = Cursor::new(Vec::with_capacity(4 * data.len()));
-    prs::decompress(&mut Cursor::new(data), &mut decompressed)?;
-    return Ok(decompressed.into_inner());
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+    use byteorder::{ByteOrder, LittleEndian};
+    use prs::Compression;
+    use read::ReadError;
+    use types::{Language, QuestType, TextMode, Version};
+    use util::TextDecoder;
+    use write::qst::{self, QstOptions};
+    use super::{decode_quest_names, extract, RawHeader};
+
+    fn raw_header(quest_name: &[u8]) -> RawHeader {
+        let mut buffer = vec![0; 32];
+        buffer[0..quest_name.len()].copy_from_slice(quest_name);
+        return RawHeader {
+            quest_type: QuestType::Online,
+            quest_number: 1,
+            quest_name: buffer,
+            quest_name_offset: 4,
+            file_name: "quest1.bin".to_string(),
+            size: 0
+        };
+    }
+
+    #[test]
+    fn quest_names_use_the_encoding_of_the_language() {
+        // "クエスト" in Shift-JIS.
+        let japanese = [0x83, 0x4E, 0x83, 0x47, 0x83, 0x58, 0x83, 0x67];
+        let mut decoder = TextDecoder::new(TextMode::Strict);
+        let headers = decode_quest_names(vec![raw_header(&japanese)], Language::Japanese, &mut decoder).unwrap();
+        assert_eq!(headers[0].quest_name, "クエスト");
+
+        let headers = decode_quest_names(vec![raw_header(b"Caf\xE9")], Language::French, &mut decoder).unwrap();
+        assert_eq!(headers[0].quest_name, "Café");
+    }
+
+    #[test]
+    fn sizes_past_the_end_of_the_data_are_rejected() {
+        let options = QstOptions {
+            version: Version::PC,
+            quest_type: QuestType::Online,
+            quest_number: 1,
+            quest_name: "Oversized".to_string(),
+            language: Language::English,
+            file_name: "quest1".to_string(),
+            compression: Compression::Fast
+        };
+        let mut data = Vec::new();
+        qst::write(&mut data, &options, &[0; 100], &[0; 100]).unwrap();
+        // The size field of the first header.
+        LittleEndian::write_u32(&mut data[56..], 0xFFFFFFFF);
+
+        match extract(&mut Cursor::new(data), &mut TextDecoder::new(TextMode::Lenient)) {
+            Err(ReadError::InvalidData) => {},
+            Err(err) => panic!("unexpected error {:?}", err),
+            Ok(_) => panic!("the oversized file was extracted")
+        }
+    }
 }
@@ -1,16 +1,195 @@
-use std::io::{Read, Seek};
-use read;
+use std::io::{Cursor, Read, Seek, SeekFrom};
+use prs::{self, DecompressStats};
+use read::{self, ReadError};
 use read::qst::{self, QstFile};
-use types::Quest;
+use read::dat::{self, DatFile};
+use read::bin::{self, BinFile};
+use types::{Episode, EpisodeSource, QstHeader, Quest, ReplacedText, TextMode, Version};
+use util::TextDecoder;
 
 // High level read method that delegates to the correct lower level read methods.
-pub fn read<T: Read + Seek>(data: &mut T) -> read::Result<Quest> {
-    let QstFile { dat, bin } = qst::read(data)?;
-
-    return Ok(Quest {
-        name: bin.quest_name,
-        short_description: bin.short_description,
-        episode: dat.episode,
-        monster_counts: dat.monster_counts,
-    });
+// Reads .qst files and standalone .dat or .bin files, which may be PRS compressed. A standalone .dat file yields a
+// quest without name or version, a standalone .bin file yields a quest without monsters.
+// Text_mode determines whether invalid units in strings are an error or are replaced and listed in replaced_text.
+pub fn read<T: Read + Seek>(data: &mut T, text_mode: TextMode) -> read::Result<Quest> {
+    let mut decoder = TextDecoder::new(text_mode);
+
+    match read_file(data, None, &mut decoder)? {
+        QuestFile::Qst(QstFile { version, headers, dat, bin, dat_compression, bin_compression }) =>
+            return Ok(new_quest(Some((version, bin, Some(bin_compression))), Some((dat, Some(dat_compression))),
+                                headers, decoder.replaced)),
+        QuestFile::Bin(version, bin, bin_compression) =>
+            return Ok(new_quest(Some((version, bin, bin_compression)), None, Vec::new(), decoder.replaced)),
+        QuestFile::Dat(dat, dat_compression) =>
+            return Ok(new_quest(None, Some((dat, dat_compression)), Vec::new(), decoder.replaced))
+    }
+}
+
+// Combines a standalone .bin and .dat file into one quest.
+pub fn read_pair<B: Read + Seek, D: Read + Seek>(bin_data: &mut B, dat_data: &mut D, text_mode: TextMode)
+                                                 -> read::Result<Quest> {
+    // The .dat file needs the episode from the .bin file.
+    let mut decoder = TextDecoder::new(text_mode);
+    let bin_file = read_file(bin_data, None, &mut decoder)?;
+    let episode = match bin_file {
+        QuestFile::Bin(_, ref bin, _) => bin.episode,
+        _ => None
+    };
+
+    match (bin_file, read_file(dat_data, episode, &mut decoder)?) {
+        (QuestFile::Bin(version, bin, bin_compression), QuestFile::Dat(dat, dat_compression)) =>
+            return Ok(new_quest(Some((version, bin, bin_compression)), Some((dat, dat_compression)), Vec::new(),
+                                decoder.replaced)),
+        _ =>
+            return Err(ReadError::InvalidData)
+    }
+}
+
+// Returns the decompressed .bin file of a .qst file or of a standalone .bin file, which may be PRS compressed.
+pub fn read_bin_data<T: Read + Seek>(data: &mut T) -> read::Result<Vec<u8>> {
+    return read_embedded_data(data, ".bin", |data| bin::detect_version(data).is_some());
+}
+
+// Returns the decompressed .dat file of a .qst file or of a standalone .dat file, which may be PRS compressed.
+pub fn read_dat_data<T: Read + Seek>(data: &mut T) -> read::Result<Vec<u8>> {
+    return read_embedded_data(data, ".dat", dat::is_dat);
+}
+
+// Extension selects the embedded file of a .qst file, is_file recognizes a standalone file.
+fn read_embedded_data<T: Read + Seek, F: Fn(&[u8]) -> bool>(data: &mut T, extension: &str, is_file: F)
+                                                          -> read::Result<Vec<u8>> {
+    let start_pos = data.seek(SeekFrom::Current(0))?;
+
+    if qst::is_qst(data)? {
+        let (_, headers, file_data) = qst::extract(data, &mut TextDecoder::new(TextMode::Lenient))?;
+        let index = headers.iter().position(|h| h.file_name.ends_with(extension)).ok_or(ReadError::InvalidData)?;
+        return Ok(prs::decompress_buf(&file_data[index], prs::DEFAULT_MAX_SIZE)?.0);
+    }
+
+    data.seek(SeekFrom::Start(start_pos))?;
+    let mut buffer = Vec::new();
+    data.read_to_end(&mut buffer)?;
+
+    if is_file(&buffer) {
+        return Ok(buffer);
+    }
+
+    match prs::decompress_buf(&buffer, prs::DEFAULT_MAX_SIZE) {
+        Ok((decompressed, _)) if is_file(&decompressed) => return Ok(decompressed),
+        _ => return Err(ReadError::InvalidData)
+    }
+}
+
+// Builds a quest from the files it was read from. Without a .bin file the quest has no version or header fields,
+// without a .dat file it has no monsters and its episode is the one the script sets.
+fn new_quest(bin: Option<(Version, BinFile, Option<DecompressStats>)>, dat: Option<(DatFile, Option<DecompressStats>)>,
+             qst_headers: Vec<QstHeader>, replaced_text: Vec<ReplacedText>) -> Quest {
+    let (episode, episode_source) = match (&bin, &dat) {
+        (_, &Some((ref dat, _))) => (dat.episode, dat.episode_source),
+        (&Some((_, BinFile { episode: Some(episode), .. }, _)), &None) => (episode, EpisodeSource::Script),
+        _ => (Episode::I, EpisodeSource::Default)
+    };
+    let mut quest = Quest {
+        version: None,
+        quest_number: None,
+        language: None,
+        object_code_offset: None,
+        function_offset_table_offset: None,
+        bin_size: None,
+        name: String::new(),
+        short_description: String::new(),
+        long_description: String::new(),
+        episode: episode,
+        episode_source: episode_source,
+        gamecube: false,
+        monster_counts: Default::default(),
+        area_monster_counts: Default::default(),
+        section_monster_counts: Default::default(),
+        wave_monster_counts: Default::default(),
+        unspawned_waves: Default::default(),
+        object_counts: Default::default(),
+        events: Vec::new(),
+        map_designations: Vec::new(),
+        replaced_text: replaced_text,
+        qst_headers: qst_headers,
+        dat_compression: None,
+        bin_compression: None
+    };
+
+    if let Some((version, bin, bin_compression)) = bin {
+        quest.version = Some(version);
+        quest.quest_number = Some(bin.quest_number);
+        quest.language = Some(bin.language);
+        quest.object_code_offset = Some(bin.object_code_offset);
+        quest.function_offset_table_offset = Some(bin.function_offset_table_offset);
+        quest.bin_size = Some(bin.size);
+        quest.name = bin.quest_name;
+        quest.short_description = bin.short_description;
+        quest.long_description = bin.long_description;
+        quest.gamecube = bin.gamecube;
+        quest.map_designations = bin.map_designations;
+        quest.bin_compression = bin_compression;
+    }
+
+    if let Some((dat, dat_compression)) = dat {
+        quest.monster_counts = dat.monster_counts;
+        quest.area_monster_counts = dat.area_monster_counts;
+        quest.section_monster_counts = dat.section_monster_counts;
+        quest.wave_monster_counts = dat.wave_monster_counts;
+        quest.unspawned_waves = dat.unspawned_waves;
+        quest.object_counts = dat.object_counts;
+        quest.events = dat.events;
+        quest.dat_compression = dat_compression;
+    }
+
+    return quest;
+}
+
+// The kinds of files a quest can be read from. Standalone files come with compression statistics if they were
+// compressed.
+enum QuestFile {
+    Qst(QstFile),
+    Bin(Version, BinFile, Option<DecompressStats>),
+    Dat(DatFile, Option<DecompressStats>)
+}
+
+// Script_episode is passed to the .dat reader for standalone .dat files.
+fn read_file<T: Read + Seek>(data: &mut T, script_episode: Option<Episode>, decoder: &mut TextDecoder)
+                             -> read::Result<QuestFile> {
+    let start_pos = data.seek(SeekFrom::Current(0))?;
+
+    if qst::is_qst(data)? {
+        return Ok(QuestFile::Qst(qst::read(data, decoder)?));
+    }
+
+    data.seek(SeekFrom::Start(start_pos))?;
+    let mut buffer = Vec::new();
+    data.read_to_end(&mut buffer)?;
+
+    if let Some(file) = read_standalone_file(&buffer, None, script_episode, decoder)? {
+        return Ok(file);
+    }
+
+    // Not an uncompressed file, try decompressing it.
+    if let Ok((decompressed, stats)) = prs::decompress_buf(&buffer, prs::DEFAULT_MAX_SIZE) {
+        if let Some(file) = read_standalone_file(&decompressed, Some(stats), script_episode, decoder)? {
+            return Ok(file);
+        }
+    }
+
+    return Err(ReadError::InvalidData);
+}
+
+// Returns None when data is neither a .bin nor a .dat file.
+fn read_standalone_file(data: &[u8], stats: Option<DecompressStats>, script_episode: Option<Episode>,
+                        decoder: &mut TextDecoder) -> read::Result<Option<QuestFile>> {
+    if let Some(version) = bin::detect_version(data) {
+        let bin = bin::read(&mut Cursor::new(data), version, decoder)?;
+        return Ok(Some(QuestFile::Bin(version, bin, stats)));
+    } else if dat::is_dat(data) {
+        let dat = dat::read(&mut Cursor::new(data), script_episode)?;
+        return Ok(Some(QuestFile::Dat(dat, stats)));
+    } else {
+        return Ok(None);
+    }
 }
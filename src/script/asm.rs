@@ -0,0 +1,487 @@
+use std::char;
+use std::fmt;
+use std::str::FromStr;
+use script::{Arg, Instruction, Script, Segment, SegmentData};
+use script::opcodes::{self, Param};
+use types::{Language, Version};
+
+#[derive(Debug)]
+pub struct AsmError {
+    // 1-based line number.
+    pub line: usize,
+    pub message: String
+}
+
+impl fmt::Display for AsmError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "line {}: {}", self.line, self.message)
+    }
+}
+
+type Result<T> = ::std::result::Result<T, String>;
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Section {
+    Code,
+    Data,
+    String
+}
+
+// Reads an assembly listing in the format disasm::disassemble writes.
+// Comments start with a semicolon. Labels are numbers followed by a colon and apply to the next segment, a segment
+// ends at the next label or section directive.
+pub fn assemble(text: &str) -> ::std::result::Result<Script, AsmError> {
+    let mut assembler = Assembler {
+        version: None,
+        quest_number: 0,
+        language: Language::Japanese,
+        quest_name: String::new(),
+        short_description: String::new(),
+        long_description: String::new(),
+        header_data: Vec::new(),
+        label_count: 0,
+        section: Section::Code,
+        segments: Vec::new(),
+        pending_labels: Vec::new(),
+        new_segment: true
+    };
+
+    for (index, line) in text.lines().enumerate() {
+        assembler.line(strip_comment(line).trim()).map_err(|message| AsmError { line: index + 1, message: message })?;
+    }
+
+    let line_count = text.lines().count();
+    assembler.finish().map_err(|message| AsmError { line: line_count, message: message })
+}
+
+struct Assembler {
+    version: Option<Version>,
+    quest_number: u16,
+    language: Language,
+    quest_name: String,
+    short_description: String,
+    long_description: String,
+    header_data: Vec<(usize, Vec<u8>)>,
+    label_count: usize,
+    section: Section,
+    segments: Vec<Segment>,
+    // Labels that apply to the next segment.
+    pending_labels: Vec<u16>,
+    // Whether the next content starts a new segment, which happens after labels and section directives.
+    new_segment: bool
+}
+
+impl Assembler {
+    fn line(&mut self, line: &str) -> Result<()> {
+        if line.is_empty() {
+            return Ok(());
+        }
+
+        if line.starts_with('.') {
+            let (directive, rest) = split_first_word(line);
+            return self.directive(directive, rest);
+        }
+
+        if line.ends_with(':') {
+            let label = parse_number::<u16>(&line[0..(line.len() - 1)])?;
+
+            if self.pending_labels.contains(&label) || self.segments.iter().any(|s| s.labels.contains(&label)) {
+                return Err(format!("label {} is defined more than once", label));
+            }
+
+            self.pending_labels.push(label);
+            self.new_segment = true;
+            return Ok(());
+        }
+
+        match self.section {
+            Section::Code => {
+                let instruction = parse_instruction(line)?;
+
+                match self.current_segment(Section::Code) {
+                    &mut SegmentData::Instructions(ref mut instructions) => instructions.push(instruction),
+                    _ => unreachable!()
+                }
+            },
+            Section::Data => {
+                let mut bytes = Vec::new();
+
+                for word in line.split_whitespace() {
+                    bytes.push(u8::from_str_radix(word, 16).map_err(|_| format!("invalid byte {:?}", word))?);
+                }
+
+                match self.current_segment(Section::Data) {
+                    &mut SegmentData::Data(ref mut data) => data.extend_from_slice(&bytes),
+                    _ => unreachable!()
+                }
+            },
+            Section::String => {
+                if !self.new_segment {
+                    return Err("a string segment contains a single string".to_string());
+                }
+
+                let string = parse_string(line)?;
+                self.start_segment(SegmentData::String(string));
+            }
+        }
+
+        return Ok(());
+    }
+
+    fn directive(&mut self, directive: &str, args: &str) -> Result<()> {
+        match directive {
+            ".version" => {
+                self.version = Some(match args {
+                    "dcgc" => Version::DCGC,
+                    "pc" => Version::PC,
+                    "bb" => Version::BB,
+                    _ => return Err(format!("unknown version {:?}", args))
+                });
+            },
+            ".quest_number" => self.quest_number = parse_number(args)?,
+            ".language" => self.language = Language::from(parse_number::<u8>(args)?),
+            ".quest_name" => self.quest_name = parse_string(args)?,
+            ".short_description" => self.short_description = parse_string(args)?,
+            ".long_description" => self.long_description = parse_string(args)?,
+            ".header_data" => {
+                let (offset, bytes) = split_first_word(args);
+                let bytes = bytes.split_whitespace()
+                    .map(|word| u8::from_str_radix(word, 16).map_err(|_| format!("invalid byte {:?}", word)))
+                    .collect::<Result<Vec<u8>>>()?;
+                self.header_data.push((parse_number(offset)?, bytes));
+            },
+            ".label_count" => self.label_count = parse_number(args)?,
+            ".code" => self.start_section(Section::Code),
+            ".data" => self.start_section(Section::Data),
+            ".string" => self.start_section(Section::String),
+            _ => return Err(format!("unknown directive {}", directive))
+        }
+
+        return Ok(());
+    }
+
+    fn start_section(&mut self, section: Section) {
+        self.section = section;
+        self.new_segment = true;
+    }
+
+    fn start_segment(&mut self, data: SegmentData) {
+        let labels = self.pending_labels.drain(..).collect();
+        self.segments.push(Segment { labels: labels, data: data });
+        self.new_segment = false;
+    }
+
+    // Returns the data of the segment new content is added to.
+    fn current_segment(&mut self, section: Section) -> &mut SegmentData {
+        if self.new_segment {
+            self.start_segment(empty_segment_data(section));
+        }
+
+        &mut self.segments.last_mut().unwrap().data
+    }
+
+    fn finish(mut self) -> Result<Script> {
+        let version = self.version.ok_or(".version is missing".to_string())?;
+
+        // Labels at the end of the listing point to the end of the object code.
+        if !self.pending_labels.is_empty() {
+            self.start_segment(SegmentData::Instructions(Vec::new()));
+        }
+
+        return Ok(Script {
+            version: version,
+            quest_number: self.quest_number,
+            language: self.language,
+            quest_name: self.quest_name,
+            short_description: self.short_description,
+            long_description: self.long_description,
+            header_data: self.header_data,
+            segments: self.segments,
+            label_count: self.label_count
+        });
+    }
+}
+
+fn empty_segment_data(section: Section) -> SegmentData {
+    match section {
+        Section::Code => SegmentData::Instructions(Vec::new()),
+        Section::Data => SegmentData::Data(Vec::new()),
+        Section::String => SegmentData::String(String::new())
+    }
+}
+
+fn parse_instruction(line: &str) -> Result<Instruction> {
+    let (mnemonic, rest) = split_first_word(line);
+    let opcode = opcodes::find_by_mnemonic(mnemonic).ok_or(format!("unknown opcode {}", mnemonic))?;
+    let words = split_args(rest)?;
+    let mut words = words.iter();
+    let mut args = Vec::new();
+
+    for &param in opcode.params {
+        match param {
+            // Lists take the remaining words, see Instruction::param.
+            Param::LabelList => {
+                for word in words.by_ref() {
+                    args.push(Arg::Label(parse_number(word)?));
+                }
+            },
+            Param::RegisterList => {
+                for word in words.by_ref() {
+                    args.push(Arg::Register(parse_register(word)?));
+                }
+            },
+            _ => {
+                let word = words.next().ok_or(format!("{} expects {} arguments", mnemonic, opcode.params.len()))?;
+                args.push(parse_arg(param, word)?);
+            }
+        }
+    }
+
+    if words.next().is_some() {
+        return Err(format!("{} expects {} arguments", mnemonic, opcode.params.len()));
+    }
+
+    return Ok(Instruction { opcode: opcode, args: args });
+}
+
+fn parse_arg(param: Param, word: &str) -> Result<Arg> {
+    Ok(match param {
+        Param::U8 => Arg::U8(parse_number(word)?),
+        Param::U16 => Arg::U16(parse_number(word)?),
+        Param::U32 => Arg::U32(parse_number(word)?),
+        Param::I32 => Arg::I32(parse_number(word)?),
+        Param::F32 => Arg::F32(word.parse().map_err(|_| format!("invalid number {:?}", word))?),
+        Param::Register | Param::RegisterList => Arg::Register(parse_register(word)?),
+        Param::Label | Param::DataLabel | Param::LabelList => Arg::Label(parse_number(word)?),
+        Param::String => Arg::String(parse_string(word)?)
+    })
+}
+
+// Parses a decimal number or a hexadecimal number starting with 0x.
+fn parse_number<T: FromStr + FromStrRadix>(word: &str) -> Result<T> {
+    let result = if word.starts_with("0x") {
+        T::from_str_radix(&word[2..], 16)
+    } else {
+        word.parse().ok()
+    };
+
+    result.ok_or(format!("invalid number {:?}", word))
+}
+
+trait FromStrRadix: Sized {
+    fn from_str_radix(string: &str, radix: u32) -> Option<Self>;
+}
+
+macro_rules! from_str_radix {
+    ($($t:ty),*) => {
+        $(impl FromStrRadix for $t {
+            fn from_str_radix(string: &str, radix: u32) -> Option<$t> {
+                <$t>::from_str_radix(string, radix).ok()
+            }
+        })*
+    };
+}
+
+from_str_radix!(u8, u16, u32, i32, usize);
+
+fn parse_register(word: &str) -> Result<u8> {
+    if word.starts_with('r') {
+        if let Ok(register) = word[1..].parse() {
+            return Ok(register);
+        }
+    }
+
+    Err(format!("invalid register {:?}", word))
+}
+
+// Parses a quoted string with the escapes disasm::quote_string writes.
+fn parse_string(word: &str) -> Result<String> {
+    if word.len() < 2 || !word.starts_with('"') || !word.ends_with('"') {
+        return Err(format!("invalid string {}", word));
+    }
+
+    let mut string = String::new();
+    let mut chars = word[1..(word.len() - 1)].chars();
+
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            string.push(c);
+            continue;
+        }
+
+        match chars.next() {
+            Some('\\') => string.push('\\'),
+            Some('"') => string.push('"'),
+            Some('n') => string.push('\n'),
+            Some('r') => string.push('\r'),
+            Some('t') => string.push('\t'),
+            Some('u') => {
+                let code: String = chars.by_ref().take_while(|&c| c != '}').collect();
+                let c = if code.starts_with('{') { u32::from_str_radix(&code[1..], 16).ok() } else { None }
+                    .and_then(char::from_u32)
+                    .ok_or(format!("invalid escape in {}", word))?;
+                string.push(c);
+            },
+            _ => return Err(format!("invalid escape in {}", word))
+        }
+    }
+
+    return Ok(string);
+}
+
+fn split_first_word(line: &str) -> (&str, &str) {
+    match line.find(char::is_whitespace) {
+        Some(index) => (&line[0..index], line[index..].trim()),
+        None => (line, "")
+    }
+}
+
+// Splits comma separated arguments, commas in strings don't separate arguments.
+fn split_args(args: &str) -> Result<Vec<&str>> {
+    let mut words = Vec::new();
+
+    if args.is_empty() {
+        return Ok(words);
+    }
+
+    let mut start = 0;
+    let mut in_string = false;
+    let mut escaped = false;
+
+    for (index, c) in args.char_indices() {
+        match c {
+            _ if escaped => escaped = false,
+            '\\' if in_string => escaped = true,
+            '"' => in_string = !in_string,
+            ',' if !in_string => {
+                words.push(args[start..index].trim());
+                start = index + 1;
+            },
+            _ => {}
+        }
+    }
+
+    if in_string {
+        return Err(format!("unterminated string in {}", args));
+    }
+
+    words.push(args[start..].trim());
+    return Ok(words);
+}
+
+// Removes a comment, semicolons in strings don't start a comment.
+fn strip_comment(line: &str) -> &str {
+    let mut in_string = false;
+    let mut escaped = false;
+
+    for (index, c) in line.char_indices() {
+        match c {
+            _ if escaped => escaped = false,
+            '\\' if in_string => escaped = true,
+            '"' => in_string = !in_string,
+            ';' if !in_string => return &line[0..index],
+            _ => {}
+        }
+    }
+
+    line
+}
+
+#[cfg(test)]
+mod tests {
+    use byteorder::{ByteOrder, LittleEndian};
+    use script::{self, disasm};
+    use types::Version;
+    use super::assemble;
+
+    fn encode(string: &str, version: Version) -> Vec<u8> {
+        match version {
+            Version::DCGC => string.chars().map(|c| c as u8).chain(Some(0)).collect(),
+            _ => string.encode_utf16().chain(Some(0)).flat_map(|unit| vec![unit as u8, (unit >> 8) as u8]).collect()
+        }
+    }
+
+    // A .bin file with unknown header bytes, strings, pushed arguments, a label list and data that isn't code.
+    fn bin_file(version: Version) -> Vec<u8> {
+        let header_size = script::header_size(version);
+        let mut object_code = Vec::new();
+        // Label 0: message 1, "Héllo\n" and set_floor_handler 3, label 1.
+        object_code.extend_from_slice(&[0x49, 1, 0, 0, 0, 0x4E]);
+        object_code.extend(encode("Héllo\n", version));
+        object_code.extend_from_slice(&[0x50, 0x49, 3, 0, 0, 0, 0x4B, 1, 0, 0x95]);
+        // Letb r1, 1 and switch_jmp r1, 1, 2.
+        object_code.extend_from_slice(&[0x0A, 1, 1, 0x40, 1, 2, 1, 0, 2, 0, 0x01]);
+        let label_1 = object_code.len();
+        // Arg_pusho 2, arg_pushr r1 and thread 0.
+        object_code.extend_from_slice(&[0x4D, 2, 0, 0x48, 1, 0x04, 0, 0, 0x01]);
+        let label_2 = object_code.len();
+        object_code.extend_from_slice(&[0x28, 0, 0]);
+        // Label 4 isn't code, label 3 is unused.
+        let label_4 = object_code.len();
+        object_code.extend_from_slice(&[0xFF, 0xFF, 0xFF, 0x07]);
+
+        let mut data = vec![0; header_size];
+        let function_offsets = [0, label_1 as i32, label_2 as i32, -1, label_4 as i32];
+        let function_offset_table_offset = header_size + object_code.len();
+        LittleEndian::write_u32(&mut data[0..], header_size as u32);
+        LittleEndian::write_u32(&mut data[4..], function_offset_table_offset as u32);
+        LittleEndian::write_u32(&mut data[8..], (function_offset_table_offset + 4 * function_offsets.len()) as u32);
+        LittleEndian::write_u32(&mut data[12..], 0xFFFFFFFF);
+
+        let strings_offset = if version == Version::BB {
+            LittleEndian::write_u16(&mut data[16..], 58);
+            data[20] = 1;
+            // Unknown fields and a shop item.
+            data[21] = 0x33;
+            LittleEndian::write_u32(&mut data[header_size - 8..], 0x00010203);
+            0x18
+        } else {
+            data[16] = 1;
+            // The unknown byte after the language.
+            data[17] = 0x33;
+            data[18] = 58;
+            0x14
+        };
+
+        let name = encode("Round trip", version);
+        data[strings_offset..(strings_offset + name.len())].copy_from_slice(&name);
+        // Garbage after the terminator of the quest name.
+        data[strings_offset + name.len() + 2] = 0x7F;
+        let short_description_offset = strings_offset + if version == Version::DCGC { 32 } else { 64 };
+        let short_description = encode("Tab\tand \"quotes\"", version);
+        data[short_description_offset..(short_description_offset + short_description.len())]
+            .copy_from_slice(&short_description);
+
+        data.extend(object_code);
+
+        for &offset in &function_offsets {
+            let mut bytes = [0; 4];
+            LittleEndian::write_i32(&mut bytes, offset);
+            data.extend_from_slice(&bytes);
+        }
+
+        return data;
+    }
+
+    #[test]
+    fn disassembled_scripts_assemble_to_identical_bytes() {
+        for &version in &[Version::DCGC, Version::PC, Version::BB] {
+            let data = bin_file(version);
+            let mut listing = Vec::new();
+            disasm::disassemble(&script::read(&data).unwrap(), &mut listing).unwrap();
+            let listing = String::from_utf8(listing).unwrap();
+
+            let expected_lines = [
+                ".header_data", "arg_pushs \"Héllo\\n\"", "switch_jmp r1, 1, 2", "arg_pusho 2", ".data"
+            ];
+
+            for expected in &expected_lines {
+                assert!(listing.contains(expected), "{} listing lacks {}:\n{}", disasm::version_name(version),
+                        expected, listing);
+            }
+
+            let written = script::write(&assemble(&listing).unwrap()).unwrap();
+            assert!(written == data, "{} listing doesn't round trip:\n{}", disasm::version_name(version), listing);
+        }
+    }
+}
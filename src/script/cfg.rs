@@ -0,0 +1,254 @@
+// Control-flow graphs of quest scripts.
+use std::io::{self, Write};
+use script::{Instruction, Script, Segment, SegmentData, JMP, RET, SWITCH_JMP, VA_CALL};
+use script::disasm;
+use script::opcodes::{self, Param};
+
+const CALL: u16 = 0x29;
+const SWITCH_CALL: u16 = 0x41;
+
+pub struct Graph {
+    pub name: String,
+    pub nodes: Vec<Node>,
+    pub edges: Vec<Edge>
+}
+
+pub struct Node {
+    pub id: String,
+    // Text shown in the node, one line per element.
+    pub lines: Vec<String>,
+    pub kind: NodeKind
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum NodeKind {
+    Code,
+    Data,
+    // A function outside the graph of a single function.
+    External
+}
+
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct Edge {
+    pub from: String,
+    pub to: String,
+    pub kind: EdgeKind
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum EdgeKind {
+    // Jmp and switch_jmp.
+    Jump,
+    // Conditional jumps, execution continues after the instruction when the condition doesn't hold.
+    Branch,
+    // Call, va_call and switch_call.
+    Call,
+    // Other instructions that take a label, mostly event handlers and threads that run later.
+    Reference,
+    // Labels passed as data.
+    Data,
+    // Execution continues in the next block.
+    FallThrough
+}
+
+// Returns a graph with a node per segment of the script, the edges are the label references of the instructions and
+// execution continuing into the next segment.
+pub fn quest_graph(script: &Script) -> Graph {
+    let mut graph = Graph { name: "quest".to_string(), nodes: Vec::new(), edges: Vec::new() };
+
+    for (index, segment) in script.segments.iter().enumerate() {
+        let kind = match segment.data {
+            SegmentData::Instructions(_) => NodeKind::Code,
+            _ => NodeKind::Data
+        };
+        let id = push_node(&mut graph, segment, kind);
+
+        if let SegmentData::Instructions(ref instructions) = segment.data {
+            for (kind, label) in references(instructions).into_iter().flat_map(|r| r.into_iter()) {
+                if let Some(target) = find_segment(script, label) {
+                    push_edge(&mut graph, &id, &target.id(), kind);
+                }
+            }
+
+            if instructions.last().map_or(true, Instruction::falls_through) {
+                if let Some(next) = script.segments.get(index + 1) {
+                    push_edge(&mut graph, &id, &next.id(), EdgeKind::FallThrough);
+                }
+            }
+        }
+    }
+
+    return graph;
+}
+
+// Returns a graph of the basic blocks of the segment with label, other segments it refers to are external nodes.
+// Returns None when no code segment has the label.
+pub fn function_graph(script: &Script, label: u16) -> Option<Graph> {
+    let index = script.segments.iter().position(|segment| segment.labels.contains(&label))?;
+    let segment = &script.segments[index];
+    let instructions = match segment.data {
+        SegmentData::Instructions(ref instructions) => instructions,
+        _ => return None
+    };
+    let id = segment.id();
+    let mut graph = Graph { name: id.clone(), nodes: Vec::new(), edges: Vec::new() };
+    let references = references(instructions);
+    let block_id = |block: usize| format!("{}.{}", id, block);
+
+    // A block ends after every instruction that can jump.
+    let mut block_starts = vec![0];
+
+    for (index, instruction) in instructions.iter().enumerate() {
+        if index + 1 < instructions.len() && ends_block(instruction) {
+            block_starts.push(index + 1);
+        }
+    }
+
+    for (block, &start) in block_starts.iter().enumerate() {
+        let end = block_starts.get(block + 1).cloned().unwrap_or(instructions.len());
+        let mut lines = if block == 0 { vec![segment_title(segment)] } else { Vec::new() };
+        lines.extend(instructions[start..end].iter().map(disasm::format_instruction));
+        graph.nodes.push(Node { id: block_id(block), lines: lines, kind: NodeKind::Code });
+
+        for &(kind, label) in references[start..end].iter().flat_map(|r| r.iter()) {
+            let to = if segment.labels.contains(&label) {
+                block_id(0)
+            } else {
+                match find_segment(script, label) {
+                    Some(target) => {
+                        let node_kind = if kind == EdgeKind::Data { NodeKind::Data } else { NodeKind::External };
+                        push_node(&mut graph, target, node_kind)
+                    },
+                    None => continue
+                }
+            };
+            push_edge(&mut graph, &block_id(block), &to, kind);
+        }
+
+        if instructions[start..end].last().map_or(true, Instruction::falls_through) {
+            if block + 1 < block_starts.len() {
+                push_edge(&mut graph, &block_id(block), &block_id(block + 1), EdgeKind::FallThrough);
+            } else if let Some(next) = script.segments.get(index + 1) {
+                let next_id = push_node(&mut graph, next, NodeKind::External);
+                push_edge(&mut graph, &block_id(block), &next_id, EdgeKind::FallThrough);
+            }
+        }
+    }
+
+    return Some(graph);
+}
+
+// Writes a graph in the Graphviz DOT language. Jumps are solid, branches are solid with an empty arrowhead, calls
+// are bold, references dashed, data references dotted and fall throughs gray.
+pub fn write_dot<W: Write>(graph: &Graph, dst: &mut W) -> io::Result<()> {
+    writeln!(dst, "digraph {} {{", quote(&graph.name))?;
+    writeln!(dst, "    node [shape=box, fontname=monospace];")?;
+
+    for node in &graph.nodes {
+        // \l ends a left aligned line.
+        let text: String = node.lines.iter().map(|line| escape(line) + "\\l").collect();
+        let shape = match node.kind {
+            NodeKind::Code => "",
+            NodeKind::Data => ", shape=note",
+            NodeKind::External => ", shape=ellipse"
+        };
+        writeln!(dst, "    {} [label=\"{}\"{}];", quote(&node.id), text, shape)?;
+    }
+
+    for edge in &graph.edges {
+        let style = match edge.kind {
+            EdgeKind::Jump => "",
+            EdgeKind::Branch => " [arrowhead=empty]",
+            EdgeKind::Call => " [style=bold]",
+            EdgeKind::Reference => " [style=dashed]",
+            EdgeKind::Data => " [style=dotted]",
+            EdgeKind::FallThrough => " [color=gray]"
+        };
+        writeln!(dst, "    {} -> {}{};", quote(&edge.from), quote(&edge.to), style)?;
+    }
+
+    return writeln!(dst, "}}");
+}
+
+// Returns the labels every instruction refers to. Labels pushed with the arg_push opcodes belong to the instruction
+// that takes them.
+fn references(instructions: &[Instruction]) -> Vec<Vec<(EdgeKind, u16)>> {
+    let mut references = Vec::with_capacity(instructions.len());
+    let mut pushed: Vec<&Instruction> = Vec::new();
+
+    for instruction in instructions {
+        if opcodes::is_arg_push(instruction.opcode) {
+            pushed.push(instruction);
+            references.push(Vec::new());
+            continue;
+        }
+
+        let labels = instruction.referenced_labels(&pushed)
+            .into_iter()
+            .filter(|&(_, label)| label <= 0xFFFF)
+            .map(|(param, label)| (edge_kind(instruction, param), label as u16))
+            .collect();
+        pushed.clear();
+        references.push(labels);
+    }
+
+    return references;
+}
+
+fn edge_kind(instruction: &Instruction, param: Param) -> EdgeKind {
+    match instruction.opcode.code {
+        _ if param == Param::DataLabel => EdgeKind::Data,
+        JMP | SWITCH_JMP => EdgeKind::Jump,
+        CALL | VA_CALL | SWITCH_CALL => EdgeKind::Call,
+        0x2A..=0x3F => EdgeKind::Branch,
+        _ => EdgeKind::Reference
+    }
+}
+
+fn ends_block(instruction: &Instruction) -> bool {
+    match instruction.opcode.code {
+        RET | JMP | SWITCH_JMP | 0x2A..=0x3F => true,
+        _ => false
+    }
+}
+
+fn find_segment(script: &Script, label: u16) -> Option<&Segment> {
+    script.segments.iter().find(|segment| segment.labels.contains(&label))
+}
+
+// Adds a node for segment unless the graph already has one, returns its ID.
+fn push_node(graph: &mut Graph, segment: &Segment, kind: NodeKind) -> String {
+    let id = segment.id();
+
+    if !graph.nodes.iter().any(|node| node.id == id) {
+        graph.nodes.push(Node { id: id.clone(), lines: vec![segment_title(segment)], kind: kind });
+    }
+
+    return id;
+}
+
+fn push_edge(graph: &mut Graph, from: &str, to: &str, kind: EdgeKind) {
+    let edge = Edge { from: from.to_string(), to: to.to_string(), kind: kind };
+
+    if !graph.edges.contains(&edge) {
+        graph.edges.push(edge);
+    }
+}
+
+// The labels of a segment, as they're written in assembly listings.
+fn segment_title(segment: &Segment) -> String {
+    if segment.labels.is_empty() {
+        return "start:".to_string();
+    }
+
+    let labels: Vec<String> = segment.labels.iter().map(|label| label.to_string()).collect();
+    return labels.join(", ") + ":";
+}
+
+fn quote(string: &str) -> String {
+    format!("\"{}\"", escape(string))
+}
+
+fn escape(string: &str) -> String {
+    string.replace('\\', "\\\\").replace('"', "\\\"")
+}
@@ -0,0 +1,288 @@
+use std::collections::{BTreeMap, HashSet};
+use std::io::{self, Cursor, Read};
+use byteorder::{ReadBytesExt, WriteBytesExt, LittleEndian};
+use encoding::{DecoderTrap, EncoderTrap};
+use read::{self, ReadError};
+use script::{Arg, Instruction, Segment, SegmentData};
+use script::opcodes::{self, Param};
+use util::TextEncoding;
+use write::{self, WriteError};
+
+// Divides object code into segments at the offsets in the function offset table and decodes them.
+// Segments are decoded as instructions unless they're referenced as data or contain something that isn't a valid
+// instruction, those are kept as raw data so the object code can always be written back exactly.
+pub fn read_segments(object_code: &[u8], function_offsets: &[i32], text_encoding: TextEncoding)
+                     -> read::Result<Vec<Segment>> {
+    let mut labels_by_offset: BTreeMap<usize, Vec<u16>> = BTreeMap::new();
+
+    for (label, &offset) in function_offsets.iter().enumerate() {
+        if offset == -1 {
+            continue;
+        }
+
+        if offset < 0 || offset as usize > object_code.len() {
+            return Err(ReadError::InvalidData);
+        }
+
+        labels_by_offset.entry(offset as usize).or_insert_with(Vec::new).push(label as u16);
+    }
+
+    if object_code.len() > 0 {
+        labels_by_offset.entry(0).or_insert_with(Vec::new);
+    }
+
+    let offsets: Vec<usize> = labels_by_offset.keys().cloned().collect();
+    let ranges: Vec<(usize, usize)> = offsets.iter()
+        .enumerate()
+        .map(|(index, &offset)| (offset, offsets.get(index + 1).cloned().unwrap_or(object_code.len())))
+        .collect();
+    let mut segments = Vec::new();
+
+    for (&(start, end), (_, labels)) in ranges.iter().zip(labels_by_offset.into_iter()) {
+        let data = &object_code[start..end];
+        let segment_data = match read_instructions(data, text_encoding) {
+            Some(instructions) => SegmentData::Instructions(instructions),
+            None => SegmentData::Data(data.to_vec())
+        };
+        segments.push(Segment { labels: labels, data: segment_data });
+    }
+
+    // Segments that are referenced as data are never executed.
+    let data_labels = data_labels(&segments);
+
+    for (&(start, end), segment) in ranges.iter().zip(segments.iter_mut()) {
+        if segment.labels.iter().any(|label| data_labels.contains(label)) {
+            let data = &object_code[start..end];
+            segment.data = match read_string_segment(data, text_encoding) {
+                Some(string) => SegmentData::String(string),
+                None => SegmentData::Data(data.to_vec())
+            };
+        }
+    }
+
+    return Ok(segments);
+}
+
+pub fn write_segment(data: &SegmentData, text_encoding: TextEncoding, dst: &mut Vec<u8>) -> write::Result<()> {
+    match *data {
+        SegmentData::Instructions(ref instructions) => {
+            for instruction in instructions {
+                write_instruction(instruction, text_encoding, dst)?;
+            }
+        },
+        SegmentData::Data(ref bytes) => dst.extend_from_slice(bytes),
+        SegmentData::String(ref string) => write_string(string, text_encoding, dst)?
+    }
+
+    return Ok(());
+}
+
+// Labels that are used as data label arguments.
+fn data_labels(segments: &[Segment]) -> HashSet<u16> {
+    let mut labels = HashSet::new();
+
+    for segment in segments {
+        if let SegmentData::Instructions(ref instructions) = segment.data {
+            for instruction in instructions {
+                for (&param, arg) in instruction.opcode.params.iter().zip(instruction.args.iter()) {
+                    if let (Param::DataLabel, &Arg::Label(label)) = (param, arg) {
+                        labels.insert(label);
+                    }
+                }
+            }
+        }
+    }
+
+    return labels;
+}
+
+// Returns None if data doesn't consist of whole instructions with known opcodes.
+fn read_instructions(data: &[u8], text_encoding: TextEncoding) -> Option<Vec<Instruction>> {
+    let mut cursor = Cursor::new(data);
+    let mut instructions = Vec::new();
+
+    while (cursor.position() as usize) < data.len() {
+        instructions.push(read_instruction(&mut cursor, text_encoding).ok()??);
+    }
+
+    return Some(instructions);
+}
+
+// Decodes instructions until the end of data or the first invalid instruction.
+pub fn read_valid_instructions(data: &[u8], text_encoding: TextEncoding) -> Vec<Instruction> {
+    let mut cursor = Cursor::new(data);
+    let mut instructions = Vec::new();
+
+    while (cursor.position() as usize) < data.len() {
+        match read_instruction(&mut cursor, text_encoding) {
+            Ok(Some(instruction)) => instructions.push(instruction),
+            _ => break
+        }
+    }
+
+    return instructions;
+}
+
+// Returns None for unknown opcodes and arguments that can't be represented in assembly.
+pub fn read_instruction(data: &mut Cursor<&[u8]>, text_encoding: TextEncoding) -> io::Result<Option<Instruction>> {
+    let mut code = data.read_u8()? as u16;
+
+    if code == 0xF8 || code == 0xF9 {
+        code = (code << 8) | data.read_u8()? as u16;
+    }
+
+    let opcode = match opcodes::find(code) {
+        Some(opcode) => opcode,
+        None => return Ok(None)
+    };
+    let mut args = Vec::new();
+
+    for &param in opcode.params {
+        match param {
+            Param::U8 => args.push(Arg::U8(data.read_u8()?)),
+            Param::U16 => args.push(Arg::U16(data.read_u16::<LittleEndian>()?)),
+            Param::U32 => args.push(Arg::U32(data.read_u32::<LittleEndian>()?)),
+            Param::I32 => args.push(Arg::I32(data.read_i32::<LittleEndian>()?)),
+            Param::F32 => {
+                let value = data.read_f32::<LittleEndian>()?;
+
+                if !value.is_finite() {
+                    return Ok(None);
+                }
+
+                args.push(Arg::F32(value));
+            },
+            Param::Register => args.push(Arg::Register(data.read_u8()?)),
+            Param::Label | Param::DataLabel => args.push(Arg::Label(data.read_u16::<LittleEndian>()?)),
+            Param::LabelList => {
+                for _ in 0..data.read_u8()? {
+                    args.push(Arg::Label(data.read_u16::<LittleEndian>()?));
+                }
+            },
+            Param::RegisterList => {
+                for _ in 0..data.read_u8()? {
+                    args.push(Arg::Register(data.read_u8()?));
+                }
+            },
+            Param::String => match read_string(data, text_encoding)? {
+                Some(string) => args.push(Arg::String(string)),
+                None => return Ok(None)
+            }
+        }
+    }
+
+    return Ok(Some(Instruction { opcode: opcode, args: args }));
+}
+
+pub fn write_instruction(instruction: &Instruction, text_encoding: TextEncoding, dst: &mut Vec<u8>)
+                         -> write::Result<()> {
+    let code = instruction.opcode.code;
+
+    if code > 0xFF {
+        dst.write_u8((code >> 8) as u8)?;
+    }
+
+    dst.write_u8(code as u8)?;
+    let mnemonic = instruction.opcode.mnemonic;
+    let mut args = instruction.args.iter();
+
+    for &param in instruction.opcode.params {
+        match param {
+            Param::LabelList | Param::RegisterList => {
+                // The remaining arguments, see Instruction::param.
+                let list: Vec<&Arg> = args.by_ref().collect();
+
+                if list.len() > 0xFF {
+                    return Err(WriteError::NumberTooLarge { field: "list length", max: 0xFF });
+                }
+
+                dst.write_u8(list.len() as u8)?;
+
+                for arg in list {
+                    write_arg(param, arg, mnemonic, text_encoding, dst)?;
+                }
+            },
+            _ => {
+                let arg = args.next().ok_or(WriteError::InvalidArgument { mnemonic: mnemonic })?;
+                write_arg(param, arg, mnemonic, text_encoding, dst)?;
+            }
+        }
+    }
+
+    if args.next().is_some() {
+        return Err(WriteError::InvalidArgument { mnemonic: mnemonic });
+    }
+
+    return Ok(());
+}
+
+// Writes a single argument, list parameters accept their element type.
+fn write_arg(param: Param, arg: &Arg, mnemonic: &'static str, text_encoding: TextEncoding, dst: &mut Vec<u8>)
+             -> write::Result<()> {
+    match (param, arg) {
+        (Param::U8, &Arg::U8(value)) => dst.write_u8(value)?,
+        (Param::U16, &Arg::U16(value)) => dst.write_u16::<LittleEndian>(value)?,
+        (Param::U32, &Arg::U32(value)) => dst.write_u32::<LittleEndian>(value)?,
+        (Param::I32, &Arg::I32(value)) => dst.write_i32::<LittleEndian>(value)?,
+        (Param::F32, &Arg::F32(value)) => dst.write_f32::<LittleEndian>(value)?,
+        (Param::Register, &Arg::Register(register)) | (Param::RegisterList, &Arg::Register(register)) =>
+            dst.write_u8(register)?,
+        (Param::Label, &Arg::Label(label)) | (Param::DataLabel, &Arg::Label(label))
+            | (Param::LabelList, &Arg::Label(label)) => dst.write_u16::<LittleEndian>(label)?,
+        (Param::String, &Arg::String(ref string)) => write_string(string, text_encoding, dst)?,
+        _ => return Err(WriteError::InvalidArgument { mnemonic: mnemonic })
+    }
+
+    return Ok(());
+}
+
+// Returns None for strings that can't be written back exactly, those are invalid or have multiple encodings.
+fn read_string<T: Read>(data: &mut T, text_encoding: TextEncoding) -> io::Result<Option<String>> {
+    let mut bytes = Vec::new();
+
+    loop {
+        let mut unit = [0; 2];
+        let unit = &mut unit[0..text_encoding.terminator_size()];
+        data.read_exact(unit)?;
+
+        if unit.iter().all(|&b| b == 0) {
+            break;
+        }
+
+        bytes.extend_from_slice(unit);
+    }
+
+    let string = match text_encoding.encoding().decode(&bytes, DecoderTrap::Strict) {
+        Ok(string) => string,
+        Err(_) => return Ok(None)
+    };
+
+    match text_encoding.encoding().encode(&string, EncoderTrap::Strict) {
+        Ok(ref encoded) if *encoded == bytes => return Ok(Some(string)),
+        _ => return Ok(None)
+    }
+}
+
+fn write_string(string: &str, text_encoding: TextEncoding, dst: &mut Vec<u8>) -> write::Result<()> {
+    if string.contains('\0') {
+        return Err(WriteError::Encoding("strings can't contain null characters".into()));
+    }
+
+    dst.extend_from_slice(&text_encoding.encoding().encode(string, EncoderTrap::Strict)?);
+
+    for _ in 0..text_encoding.terminator_size() {
+        dst.write_u8(0)?;
+    }
+
+    return Ok(());
+}
+
+// Returns the string if data is exactly one null terminated string.
+fn read_string_segment(data: &[u8], text_encoding: TextEncoding) -> Option<String> {
+    let mut cursor = Cursor::new(data);
+
+    match read_string(&mut cursor, text_encoding) {
+        Ok(Some(string)) if cursor.position() as usize == data.len() => Some(string),
+        _ => None
+    }
+}
@@ -0,0 +1,129 @@
+use std::io::{self, Write};
+use script::{Arg, Instruction, Script, SegmentData};
+use types::Version;
+
+// Writes a script as an assembly listing.
+// The listing starts with directives for the header fields, followed by the segments. Every segment starts with its
+// labels and is placed in a .code, .data or .string section. Instructions are written as the mnemonic followed by
+// comma separated arguments, lists are written as the remaining arguments.
+pub fn disassemble<W: Write>(script: &Script, dst: &mut W) -> io::Result<()> {
+    writeln!(dst, ".version {}", version_name(script.version))?;
+    writeln!(dst, ".quest_number {}", script.quest_number)?;
+    writeln!(dst, ".language {}", u8::from(script.language))?;
+    writeln!(dst, ".quest_name {}", quote_string(&script.quest_name))?;
+    writeln!(dst, ".short_description {}", quote_string(&script.short_description))?;
+    writeln!(dst, ".long_description {}", quote_string(&script.long_description))?;
+
+    for &(offset, ref bytes) in &script.header_data {
+        write!(dst, ".header_data {}", offset)?;
+        write_bytes(dst, bytes, " ")?;
+    }
+
+    let label_count = script.segments.iter()
+        .flat_map(|s| s.labels.iter())
+        .map(|&label| label as usize + 1)
+        .max()
+        .unwrap_or(0);
+
+    if script.label_count != label_count {
+        writeln!(dst, ".label_count {}", script.label_count)?;
+    }
+
+    let mut section = "";
+
+    for segment in &script.segments {
+        let segment_section = match segment.data {
+            SegmentData::Instructions(_) => ".code",
+            SegmentData::Data(_) => ".data",
+            SegmentData::String(_) => ".string"
+        };
+
+        if segment_section != section {
+            writeln!(dst, "\n{}", segment_section)?;
+            section = segment_section;
+        }
+
+        for label in &segment.labels {
+            writeln!(dst, "{}:", label)?;
+        }
+
+        match segment.data {
+            SegmentData::Instructions(ref instructions) => {
+                for instruction in instructions {
+                    writeln!(dst, "    {}", format_instruction(instruction))?;
+                }
+            },
+            SegmentData::Data(ref bytes) => {
+                for line in bytes.chunks(16) {
+                    write!(dst, "   ")?;
+                    write_bytes(dst, line, " ")?;
+                }
+            },
+            SegmentData::String(ref string) => writeln!(dst, "    {}", quote_string(string))?
+        }
+    }
+
+    return Ok(());
+}
+
+pub fn version_name(version: Version) -> &'static str {
+    match version {
+        Version::DCGC => "dcgc",
+        Version::PC => "pc",
+        Version::BB => "bb"
+    }
+}
+
+pub fn format_instruction(instruction: &Instruction) -> String {
+    let args: Vec<String> = instruction.args.iter().map(format_arg).collect();
+
+    if args.is_empty() {
+        instruction.opcode.mnemonic.to_string()
+    } else {
+        format!("{} {}", instruction.opcode.mnemonic, args.join(", "))
+    }
+}
+
+fn format_arg(arg: &Arg) -> String {
+    match *arg {
+        Arg::U8(value) => value.to_string(),
+        Arg::U16(value) => value.to_string(),
+        Arg::U32(value) => value.to_string(),
+        Arg::I32(value) => value.to_string(),
+        // Debug formatting always includes a decimal point and is precise enough to read back the same value.
+        Arg::F32(value) => format!("{:?}", value),
+        Arg::Register(register) => format!("r{}", register),
+        Arg::Label(label) => label.to_string(),
+        Arg::String(ref string) => quote_string(string)
+    }
+}
+
+// Writes bytes as hexadecimal numbers, each preceded by separator, and ends the line.
+fn write_bytes<W: Write>(dst: &mut W, bytes: &[u8], separator: &str) -> io::Result<()> {
+    for byte in bytes {
+        write!(dst, "{}{:02x}", separator, byte)?;
+    }
+
+    writeln!(dst)
+}
+
+// Quotes a string, backslashes, quotes and control characters are escaped.
+pub fn quote_string(string: &str) -> String {
+    let mut quoted = String::with_capacity(string.len() + 2);
+    quoted.push('"');
+
+    for c in string.chars() {
+        match c {
+            '\\' => quoted.push_str("\\\\"),
+            '"' => quoted.push_str("\\\""),
+            '\n' => quoted.push_str("\\n"),
+            '\r' => quoted.push_str("\\r"),
+            '\t' => quoted.push_str("\\t"),
+            c if c.is_control() => quoted.push_str(&format!("\\u{{{:x}}}", c as u32)),
+            c => quoted.push(c)
+        }
+    }
+
+    quoted.push('"');
+    return quoted;
+}
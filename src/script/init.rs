@@ -0,0 +1,118 @@
+// Information from the initialization function, the function at label 0 that the game runs when the quest starts.
+use std::collections::HashMap;
+use script::{self, Arg, Instruction};
+use script::opcodes::Param;
+use types::{AreaVariant, Episode};
+use util::TextEncoding;
+
+const SET_EPISODE: u16 = 0xF8BC;
+const MAP_DESIGNATE: u16 = 0xC4;
+const MAP_DESIGNATE_EX: u16 = 0xF80D;
+const BB_MAP_DESIGNATE: u16 = 0xF951;
+
+// Returns the instructions of the initialization function up to the next label. Decoding stops at the first invalid
+// instruction.
+pub fn read_function(object_code: &[u8], function_offsets: &[i32], text_encoding: TextEncoding) -> Vec<Instruction> {
+    return script::read_function(object_code, function_offsets, 0, text_encoding);
+}
+
+// Returns the episode set with set_episode, None when the function doesn't set it. Only GameCube and Blue Burst
+// quests set their episode.
+pub fn episode(instructions: &[Instruction]) -> Option<Episode> {
+    for instruction in instructions {
+        if instruction.opcode.code == SET_EPISODE {
+            return match instruction.args[0] {
+                Arg::U32(0) => Some(Episode::I),
+                Arg::U32(1) => Some(Episode::II),
+                Arg::U32(2) => Some(Episode::IV),
+                _ => None
+            };
+        }
+    }
+
+    return None;
+}
+
+// Returns the maps loaded with the map designate opcodes, in order.
+// Map_designate and map_designate_ex take the first of four registers with the area in the first register.
+// Map_designate has the variant in the third register, map_designate_ex takes the area, floor, major variant and
+// minor variant and its variant is the last one. Register values are tracked through the constant assignments that
+// precede the instruction, designations with registers of unknown value are skipped. Bb_map_designate takes the area
+// and variant directly.
+pub fn map_designations(instructions: &[Instruction]) -> Vec<AreaVariant> {
+    let mut registers: HashMap<u8, u32> = HashMap::new();
+    let mut designations = Vec::new();
+
+    for instruction in instructions {
+        match (instruction.opcode.mnemonic, &instruction.args[..]) {
+            ("leti", &[Arg::Register(register), Arg::I32(value)]) => { registers.insert(register, value as u32); },
+            ("letb", &[Arg::Register(register), Arg::U8(value)]) => { registers.insert(register, value as u32); },
+            ("letw", &[Arg::Register(register), Arg::U16(value)]) => { registers.insert(register, value as u32); },
+            ("set", &[Arg::Register(register)]) => { registers.insert(register, 1); },
+            ("clear", &[Arg::Register(register)]) => { registers.insert(register, 0); },
+            _ => match instruction.opcode.code {
+                MAP_DESIGNATE | MAP_DESIGNATE_EX => if let Arg::Register(register) = instruction.args[0] {
+                    let variant_register = if instruction.opcode.code == MAP_DESIGNATE { 2 } else { 3 };
+                    let area = registers.get(&register).cloned();
+                    let variant = register.checked_add(variant_register).and_then(|r| registers.get(&r).cloned());
+
+                    if let (Some(area), Some(variant)) = (area, variant) {
+                        designations.push(AreaVariant { area: area, variant: variant });
+                    }
+                },
+                BB_MAP_DESIGNATE => match (&instruction.args[0], &instruction.args[2]) {
+                    (&Arg::U8(area), &Arg::U8(variant)) =>
+                        designations.push(AreaVariant { area: area as u32, variant: variant as u32 }),
+                    _ => {}
+                },
+                // Other instructions can change registers in ways that aren't tracked.
+                _ => for (&param, arg) in instruction.opcode.params.iter().zip(instruction.args.iter()) {
+                    if let (Param::Register, &Arg::Register(register)) = (param, arg) {
+                        registers.remove(&register);
+                    }
+                }
+            }
+        }
+    }
+
+    return designations;
+}
+
+#[cfg(test)]
+mod tests {
+    use script::{Arg, Instruction};
+    use script::opcodes;
+    use types::AreaVariant;
+    use super::*;
+
+    fn instruction(mnemonic: &str, args: Vec<Arg>) -> Instruction {
+        Instruction { opcode: opcodes::find_by_mnemonic(mnemonic).unwrap(), args: args }
+    }
+
+    // Sets r10 to r13 to 5, 1, 2 and 3.
+    fn set_registers() -> Vec<Instruction> {
+        (0..4).map(|i| instruction("leti", vec![Arg::Register(10 + i), Arg::I32([5, 1, 2, 3][i as usize])])).collect()
+    }
+
+    #[test]
+    fn map_designate_takes_the_variant_from_the_third_register() {
+        let mut instructions = set_registers();
+        instructions.push(instruction("map_designate", vec![Arg::Register(10)]));
+        assert_eq!(map_designations(&instructions), vec![AreaVariant { area: 5, variant: 2 }]);
+    }
+
+    #[test]
+    fn map_designate_ex_takes_the_variant_from_the_fourth_register() {
+        let mut instructions = set_registers();
+        instructions.push(instruction("map_designate_ex", vec![Arg::Register(10)]));
+        assert_eq!(map_designations(&instructions), vec![AreaVariant { area: 5, variant: 3 }]);
+    }
+
+    #[test]
+    fn bb_map_designate_takes_its_arguments_directly() {
+        let instructions = vec![
+            instruction("bb_map_designate", vec![Arg::U8(5), Arg::U16(1), Arg::U8(2), Arg::U8(3)])
+        ];
+        assert_eq!(map_designations(&instructions), vec![AreaVariant { area: 5, variant: 2 }]);
+    }
+}
@@ -0,0 +1,347 @@
+pub mod opcodes;
+pub mod disasm;
+pub mod asm;
+pub mod init;
+pub mod text;
+pub mod validate;
+pub mod cfg;
+mod code;
+
+use std::io::{Cursor, Write};
+use byteorder::{ByteOrder, WriteBytesExt, LittleEndian};
+use read::{self, ReadError};
+use read::bin;
+use types::{Language, TextMode, Version};
+use util::{self, TextDecoder, TextEncoding};
+use write::{self, WriteError};
+use self::opcodes::{Opcode, Param};
+
+pub const RET: u16 = 0x01;
+pub const VA_CALL: u16 = 0x07;
+pub const JMP: u16 = 0x28;
+pub const SWITCH_JMP: u16 = 0x40;
+
+// A quest script is the object code of a .bin file together with the header fields.
+// The function offset table divides the object code into segments, every entry of the table is a label that points
+// to the start of a segment. Most segments contain instructions, segments that are referenced as data or can't be
+// decoded contain raw data.
+pub struct Script {
+    pub version: Version,
+    pub quest_number: u16,
+    pub language: Language,
+    pub quest_name: String,
+    pub short_description: String,
+    pub long_description: String,
+    // Header bytes that differ from the header written for the fields above, as offset and bytes. Unknown fields and
+    // data after the string terminators end up here so the header can be written back exactly.
+    pub header_data: Vec<(usize, Vec<u8>)>,
+    pub segments: Vec<Segment>,
+    // Number of entries in the function offset table, entries without a label contain -1.
+    pub label_count: usize
+}
+
+impl Script {
+    pub fn text_encoding(&self) -> TextEncoding {
+        TextEncoding::for_quest(self.version, self.language)
+    }
+}
+
+pub struct Segment {
+    // Segments without labels can only be reached from the preceding segment.
+    pub labels: Vec<u16>,
+    pub data: SegmentData
+}
+
+impl Segment {
+    // Identifies the segment by its lowest label, or "start" for code before the first label.
+    pub fn id(&self) -> String {
+        self.labels.iter().min().map(|label| label.to_string()).unwrap_or("start".to_string())
+    }
+}
+
+pub enum SegmentData {
+    Instructions(Vec<Instruction>),
+    Data(Vec<u8>),
+    // A single null terminated string.
+    String(String)
+}
+
+pub struct Instruction {
+    pub opcode: &'static Opcode,
+    pub args: Vec<Arg>
+}
+
+impl Instruction {
+    // Returns the parameter of the argument at index, lists are always the last parameter and contain the remaining
+    // arguments.
+    pub fn param(&self, index: usize) -> Param {
+        let params = self.opcode.params;
+        return params[index.min(params.len() - 1)];
+    }
+
+    // Returns the label an arg_push instruction pushes for a stack parameter of type Label or DataLabel.
+    pub fn pushed_label(&self, param: Param) -> Option<u32> {
+        if param != Param::Label && param != Param::DataLabel {
+            return None;
+        }
+
+        match self.args.first() {
+            Some(&Arg::U8(value)) => Some(value as u32),
+            Some(&Arg::U16(value)) => Some(value as u32),
+            Some(&Arg::I32(value)) => Some(value as u32),
+            _ => None
+        }
+    }
+
+    // Returns the labels the instruction refers to with the parameter they're passed as. Pushed are the arg_push
+    // instructions before it, the labels they push for its stack parameters only count when as many arguments were
+    // pushed as it takes. Arg_pusho pushes the address of a label, which counts as a Label parameter.
+    pub fn referenced_labels(&self, pushed: &[&Instruction]) -> Vec<(Param, u32)> {
+        let mut labels = Vec::new();
+
+        for (index, arg) in self.args.iter().enumerate() {
+            if let Arg::Label(label) = *arg {
+                labels.push((self.param(index), label as u32));
+            }
+        }
+
+        let stack_params = self.opcode.stack_params;
+
+        if pushed.len() == stack_params.len() {
+            for (&param, push) in stack_params.iter().zip(pushed) {
+                if let Some(label) = push.pushed_label(param) {
+                    labels.push((param, label));
+                }
+            }
+        }
+
+        for push in pushed {
+            if let Some(&Arg::Label(label)) = push.args.first() {
+                labels.push((Param::Label, label as u32));
+            }
+        }
+
+        return labels;
+    }
+
+    // Whether execution continues after the instruction, which it doesn't after ret, jmp and switch_jmp.
+    pub fn falls_through(&self) -> bool {
+        ![RET, JMP, SWITCH_JMP].contains(&self.opcode.code)
+    }
+}
+
+// Instruction arguments, lists are stored as multiple arguments of the same kind.
+#[derive(Clone, PartialEq, Debug)]
+pub enum Arg {
+    U8(u8),
+    U16(u16),
+    U32(u32),
+    I32(i32),
+    F32(f32),
+    Register(u8),
+    Label(u16),
+    String(String)
+}
+
+// Reads the header and object code of a decompressed .bin file.
+pub fn read(data: &[u8]) -> read::Result<Script> {
+    let version = bin::detect_version(data).ok_or(ReadError::InvalidData)?;
+    // Replaced units don't round trip, header_data restores their bytes.
+    let mut decoder = TextDecoder::new(TextMode::Lenient);
+    let bin_file = bin::read(&mut Cursor::new(data), version, &mut decoder)?;
+    let object_code_offset = bin_file.object_code_offset as usize;
+    let function_offset_table_offset = bin_file.function_offset_table_offset as usize;
+
+    // The function offset table runs until the end of the file, labels are 16-bit.
+    if function_offset_table_offset < object_code_offset
+        || function_offset_table_offset > data.len()
+        || (data.len() - function_offset_table_offset) % 4 != 0
+        || (data.len() - function_offset_table_offset) / 4 > 0x10000 {
+        return Err(ReadError::InvalidData);
+    }
+
+    let function_offsets: Vec<i32> = data[function_offset_table_offset..]
+        .chunks(4)
+        .map(LittleEndian::read_i32)
+        .collect();
+    let text_encoding = TextEncoding::for_quest(version, bin_file.language);
+    let segments = code::read_segments(&data[object_code_offset..function_offset_table_offset],
+                                       &function_offsets, text_encoding)?;
+
+    let mut script = Script {
+        version: version,
+        quest_number: bin_file.quest_number,
+        language: bin_file.language,
+        quest_name: bin_file.quest_name,
+        short_description: bin_file.short_description,
+        long_description: bin_file.long_description,
+        header_data: Vec::new(),
+        segments: segments,
+        label_count: function_offsets.len()
+    };
+
+    // Strings that can't be written back are left empty, their bytes end up in header_data.
+    let (name_size, short_description_size, long_description_size) = string_field_sizes(version);
+
+    if util::write_string(&script.quest_name, &mut vec![0; name_size], text_encoding, "quest name").is_err() {
+        script.quest_name.clear();
+    }
+    if util::write_string(&script.short_description, &mut vec![0; short_description_size], text_encoding,
+                          "short description").is_err() {
+        script.short_description.clear();
+    }
+    if util::write_string(&script.long_description, &mut vec![0; long_description_size], text_encoding,
+                          "long description").is_err() {
+        script.long_description.clear();
+    }
+
+    let header = &data[0..object_code_offset];
+    let written_header = write_header(&script, data.len() as u32, function_offset_table_offset as u32)
+        .map_err(|_| ReadError::InvalidData)?;
+    script.header_data = header_differences(header, &written_header);
+    return Ok(script);
+}
+
+// Returns the instructions of the function at label up to the next label. Decoding stops at the first invalid
+// instruction, so the result is empty when label doesn't point to code.
+pub fn read_function(object_code: &[u8], function_offsets: &[i32], label: u16, text_encoding: TextEncoding)
+                     -> Vec<Instruction> {
+    let start = match function_offsets.get(label as usize) {
+        Some(&offset) if offset >= 0 && offset as usize <= object_code.len() => offset as usize,
+        _ => return Vec::new()
+    };
+    let end = function_offsets.iter()
+        .filter(|&&offset| offset >= 0 && offset as usize > start)
+        .map(|&offset| offset as usize)
+        .min()
+        .unwrap_or(object_code.len())
+        .min(object_code.len());
+    return code::read_valid_instructions(&object_code[start..end], text_encoding);
+}
+
+// Writes a decompressed .bin file, the layout bin::read expects.
+pub fn write(script: &Script) -> write::Result<Vec<u8>> {
+    let mut object_code = Vec::new();
+    let mut function_offsets = vec![-1; script.label_count];
+
+    for segment in &script.segments {
+        for &label in &segment.labels {
+            if label as usize >= function_offsets.len() {
+                function_offsets.resize(label as usize + 1, -1);
+            }
+
+            function_offsets[label as usize] = object_code.len() as i32;
+        }
+
+        code::write_segment(&segment.data, script.text_encoding(), &mut object_code)?;
+    }
+
+    let header_size = header_size(script.version);
+    let function_offset_table_offset = header_size + object_code.len();
+    let size = function_offset_table_offset + 4 * function_offsets.len();
+    let mut data = write_header(script, size as u32, function_offset_table_offset as u32)?;
+
+    for &(offset, ref bytes) in &script.header_data {
+        if offset + bytes.len() > header_size {
+            return Err(WriteError::NumberTooLarge { field: "header data offset", max: header_size as u32 });
+        }
+
+        data[offset..(offset + bytes.len())].copy_from_slice(bytes);
+    }
+
+    data.extend_from_slice(&object_code);
+
+    for offset in function_offsets {
+        data.write_i32::<LittleEndian>(offset)?;
+    }
+
+    return Ok(data);
+}
+
+pub fn header_size(version: Version) -> usize {
+    match version {
+        Version::DCGC => 0x1D4,
+        Version::PC => 0x394,
+        Version::BB => 0x122C
+    }
+}
+
+// Offset of the quest name, which is followed by the short and long description.
+fn string_fields_offset(version: Version) -> usize {
+    if version == Version::BB { 0x18 } else { 0x14 }
+}
+
+// Sizes in bytes of the quest name, short description and long description fields.
+fn string_field_sizes(version: Version) -> (usize, usize, usize) {
+    let char_size = if version == Version::DCGC { 1 } else { 2 };
+    return (32 * char_size, 128 * char_size, 288 * char_size);
+}
+
+// Writes the header without header_data, unknown fields are written as they usually appear.
+fn write_header(script: &Script, size: u32, function_offset_table_offset: u32) -> write::Result<Vec<u8>> {
+    let version = script.version;
+    let mut header = Vec::with_capacity(header_size(version));
+    header.write_u32::<LittleEndian>(header_size(version) as u32)?;
+    header.write_u32::<LittleEndian>(function_offset_table_offset)?;
+    header.write_u32::<LittleEndian>(size)?;
+    header.write_u32::<LittleEndian>(0xFFFFFFFF)?;
+
+    match version {
+        Version::DCGC | Version::PC => {
+            let max_quest_number = if version == Version::DCGC { 0xFF } else { 0xFFFF };
+
+            if script.quest_number > max_quest_number {
+                return Err(WriteError::NumberTooLarge { field: "quest number", max: max_quest_number as u32 });
+            }
+
+            header.write_u8(u8::from(script.language))?;
+            header.write_u8(0)?;
+            header.write_u16::<LittleEndian>(script.quest_number)?;
+        },
+        Version::BB => {
+            header.write_u16::<LittleEndian>(script.quest_number)?;
+            header.write_u16::<LittleEndian>(0)?;
+            header.write_u8(u8::from(script.language))?;
+            header.write_all(&[0; 3])?;
+        }
+    }
+
+    let text_encoding = script.text_encoding();
+    let (name_size, short_description_size, long_description_size) = string_field_sizes(version);
+    let mut buffer = vec![0; name_size];
+    util::write_string(&script.quest_name, &mut buffer, text_encoding, "quest name")?;
+    header.extend_from_slice(&buffer);
+    buffer = vec![0; short_description_size];
+    util::write_string(&script.short_description, &mut buffer, text_encoding, "short description")?;
+    header.extend_from_slice(&buffer);
+    buffer = vec![0; long_description_size];
+    util::write_string(&script.long_description, &mut buffer, text_encoding, "long description")?;
+    header.extend_from_slice(&buffer);
+
+    // The rest of the Blue Burst header contains an unknown field and the shop item list.
+    header.resize(header_size(version), 0);
+    return Ok(header);
+}
+
+// Returns the ranges in which header differs from written_header. Ranges that are less than 8 bytes apart are
+// merged.
+fn header_differences(header: &[u8], written_header: &[u8]) -> Vec<(usize, Vec<u8>)> {
+    let mut differences: Vec<(usize, Vec<u8>)> = Vec::new();
+
+    for (offset, (&byte, &written_byte)) in header.iter().zip(written_header.iter()).enumerate() {
+        if byte == written_byte {
+            continue;
+        }
+
+        if let Some(&mut (start, ref mut bytes)) = differences.last_mut() {
+            if offset - (start + bytes.len()) < 8 {
+                bytes.extend_from_slice(&header[(start + bytes.len())..(offset + 1)]);
+                continue;
+            }
+        }
+
+        differences.push((offset, vec![byte]));
+    }
+
+    return differences;
+}
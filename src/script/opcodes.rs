@@ -0,0 +1,584 @@
+// Opcode table of the quest script language.
+// Opcodes are a single byte, except for those starting with 0xF8 or 0xF9 which are followed by a second byte. The
+// table describes the encoding used by version 2 and later, Dreamcast version 1 quests pass some arguments inline
+// instead of on the argument stack.
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Param {
+    U8,
+    U16,
+    U32,
+    I32,
+    F32,
+    // A single byte register number, written as r0 to r255.
+    Register,
+    // A 2 byte index into the function offset table.
+    Label,
+    // Like Label, but the label points to data instead of code.
+    DataLabel,
+    // A 1 byte count followed by that many labels.
+    LabelList,
+    // A 1 byte count followed by that many registers.
+    RegisterList,
+    // A null terminated string, 8-bit on Dreamcast and GameCube and UTF-16 on PC and Blue Burst.
+    String
+}
+
+pub struct Opcode {
+    pub code: u16,
+    pub mnemonic: &'static str,
+    // Arguments that are encoded directly after the opcode.
+    pub params: &'static [Param],
+    // Arguments that are pushed onto the argument stack with the arg_push opcodes before the instruction.
+    pub stack_params: &'static [Param]
+}
+
+pub fn find(code: u16) -> Option<&'static Opcode> {
+    return OPCODES.binary_search_by_key(&code, |o| o.code).ok().map(|index| &OPCODES[index]);
+}
+
+pub fn find_by_mnemonic(mnemonic: &str) -> Option<&'static Opcode> {
+    return OPCODES.iter().find(|o| o.mnemonic == mnemonic);
+}
+
+// Returns true for the opcodes that push an argument onto the argument stack.
+pub fn is_arg_push(opcode: &Opcode) -> bool {
+    return opcode.code >= 0x48 && opcode.code <= 0x4E;
+}
+
+macro_rules! op {
+    ($code:expr, $mnemonic:expr, [$($param:ident),*], [$($stack_param:ident),*]) => {
+        Opcode {
+            code: $code,
+            mnemonic: $mnemonic,
+            params: &[$(Param::$param),*],
+            stack_params: &[$(Param::$stack_param),*]
+        }
+    };
+}
+
+// Sorted by code.
+static OPCODES: &'static [Opcode] = &[
+    op!(0x00, "nop", [], []),
+    op!(0x01, "ret", [], []),
+    op!(0x02, "sync", [], []),
+    op!(0x03, "exit", [I32], []),
+    op!(0x04, "thread", [Label], []),
+    op!(0x05, "va_start", [], []),
+    op!(0x06, "va_end", [], []),
+    op!(0x07, "va_call", [Label], []),
+    op!(0x08, "let", [Register, Register], []),
+    op!(0x09, "leti", [Register, I32], []),
+    op!(0x0A, "letb", [Register, U8], []),
+    op!(0x0B, "letw", [Register, U16], []),
+    op!(0x0C, "leta", [Register, Register], []),
+    op!(0x0D, "leto", [Register, Label], []),
+    op!(0x10, "set", [Register], []),
+    op!(0x11, "clear", [Register], []),
+    op!(0x12, "rev", [Register], []),
+    op!(0x13, "gset", [U16], []),
+    op!(0x14, "gclear", [U16], []),
+    op!(0x15, "grev", [U16], []),
+    op!(0x16, "glet", [U16, Register], []),
+    op!(0x17, "gget", [U16, Register], []),
+    op!(0x18, "add", [Register, Register], []),
+    op!(0x19, "addi", [Register, I32], []),
+    op!(0x1A, "sub", [Register, Register], []),
+    op!(0x1B, "subi", [Register, I32], []),
+    op!(0x1C, "mul", [Register, Register], []),
+    op!(0x1D, "muli", [Register, I32], []),
+    op!(0x1E, "div", [Register, Register], []),
+    op!(0x1F, "divi", [Register, I32], []),
+    op!(0x20, "and", [Register, Register], []),
+    op!(0x21, "andi", [Register, I32], []),
+    op!(0x22, "or", [Register, Register], []),
+    op!(0x23, "ori", [Register, I32], []),
+    op!(0x24, "xor", [Register, Register], []),
+    op!(0x25, "xori", [Register, I32], []),
+    op!(0x26, "mod", [Register, Register], []),
+    op!(0x27, "modi", [Register, I32], []),
+    op!(0x28, "jmp", [Label], []),
+    op!(0x29, "call", [Label], []),
+    op!(0x2A, "jmp_on", [Label, RegisterList], []),
+    op!(0x2B, "jmp_off", [Label, RegisterList], []),
+    op!(0x2C, "jmp_=", [Register, Register, Label], []),
+    op!(0x2D, "jmpi_=", [Register, I32, Label], []),
+    op!(0x2E, "jmp_!=", [Register, Register, Label], []),
+    op!(0x2F, "jmpi_!=", [Register, I32, Label], []),
+    op!(0x30, "ujmp_>", [Register, Register, Label], []),
+    op!(0x31, "ujmpi_>", [Register, U32, Label], []),
+    op!(0x32, "jmp_>", [Register, Register, Label], []),
+    op!(0x33, "jmpi_>", [Register, I32, Label], []),
+    op!(0x34, "ujmp_<", [Register, Register, Label], []),
+    op!(0x35, "ujmpi_<", [Register, U32, Label], []),
+    op!(0x36, "jmp_<", [Register, Register, Label], []),
+    op!(0x37, "jmpi_<", [Register, I32, Label], []),
+    op!(0x38, "ujmp_>=", [Register, Register, Label], []),
+    op!(0x39, "ujmpi_>=", [Register, U32, Label], []),
+    op!(0x3A, "jmp_>=", [Register, Register, Label], []),
+    op!(0x3B, "jmpi_>=", [Register, I32, Label], []),
+    op!(0x3C, "ujmp_<=", [Register, Register, Label], []),
+    op!(0x3D, "ujmpi_<=", [Register, U32, Label], []),
+    op!(0x3E, "jmp_<=", [Register, Register, Label], []),
+    op!(0x3F, "jmpi_<=", [Register, I32, Label], []),
+    op!(0x40, "switch_jmp", [Register, LabelList], []),
+    op!(0x41, "switch_call", [Register, LabelList], []),
+    op!(0x42, "stack_push", [Register], []),
+    op!(0x43, "stack_pop", [Register], []),
+    op!(0x44, "stack_pushm", [Register, U32], []),
+    op!(0x45, "stack_popm", [Register, U32], []),
+    op!(0x48, "arg_pushr", [Register], []),
+    op!(0x49, "arg_pushl", [I32], []),
+    op!(0x4A, "arg_pushb", [U8], []),
+    op!(0x4B, "arg_pushw", [U16], []),
+    op!(0x4C, "arg_pusha", [Register], []),
+    op!(0x4D, "arg_pusho", [Label], []),
+    op!(0x4E, "arg_pushs", [String], []),
+    op!(0x50, "message", [], [I32, String]),
+    op!(0x51, "list", [], [Register, String]),
+    op!(0x52, "fadein", [], []),
+    op!(0x53, "fadeout", [], []),
+    op!(0x54, "se", [], [I32]),
+    op!(0x55, "bgm", [], [I32]),
+    op!(0x58, "enable", [], [I32]),
+    op!(0x59, "disable", [], [I32]),
+    op!(0x5A, "window_msg", [], [String]),
+    op!(0x5B, "add_msg", [], [String]),
+    op!(0x5C, "mesend", [], []),
+    op!(0x5D, "gettime", [Register], []),
+    op!(0x5E, "winend", [], []),
+    op!(0x60, "npc_crt", [], [Register, I32]),
+    op!(0x61, "npc_stop", [], [I32]),
+    op!(0x62, "npc_play", [], [I32]),
+    op!(0x63, "npc_kill", [], [I32]),
+    op!(0x64, "npc_nont", [], []),
+    op!(0x65, "npc_talk", [], []),
+    op!(0x66, "npc_crp", [], [Register]),
+    op!(0x68, "create_pipe", [], [I32]),
+    op!(0x69, "p_hpstat", [], [Register, I32]),
+    op!(0x6A, "p_dead", [], [Register, I32]),
+    op!(0x6B, "p_disablewarp", [], []),
+    op!(0x6C, "p_enablewarp", [], []),
+    op!(0x6D, "p_move", [], [Register]),
+    op!(0x6E, "p_look", [], [I32]),
+    op!(0x70, "p_action_disable", [], []),
+    op!(0x71, "p_action_enable", [], []),
+    op!(0x72, "disable_movement1", [], [I32]),
+    op!(0x73, "enable_movement1", [], [I32]),
+    op!(0x74, "p_noncol", [], []),
+    op!(0x75, "p_col", [], []),
+    op!(0x76, "p_setpos", [], [I32, Register]),
+    op!(0x77, "p_return_guild", [], []),
+    op!(0x78, "p_talk_guild", [], [I32]),
+    op!(0x79, "npc_talk_pl", [], [Register]),
+    op!(0x7A, "npc_talk_kill", [], [I32]),
+    op!(0x7B, "npc_crtpk", [], [Register, I32]),
+    op!(0x7C, "npc_crppk", [], [Register, I32]),
+    op!(0x7D, "npc_crptalk", [], [Register]),
+    op!(0x7E, "p_look_at", [], [I32, I32]),
+    op!(0x7F, "npc_crp_id", [], [Register, I32]),
+    op!(0x80, "cam_quake", [], []),
+    op!(0x81, "cam_adj", [], []),
+    op!(0x82, "cam_zmin", [], []),
+    op!(0x83, "cam_zmout", [], []),
+    op!(0x84, "cam_pan", [], [Register, I32]),
+    op!(0x85, "game_lev_super", [], []),
+    op!(0x86, "game_lev_reset", [], []),
+    op!(0x87, "pos_pipe", [], [Register]),
+    op!(0x88, "if_zone_clear", [Register, Register], []),
+    op!(0x89, "chk_ene_num", [Register], []),
+    op!(0x8A, "unhide_obj", [Register], []),
+    op!(0x8B, "unhide_ene", [Register], []),
+    op!(0x8C, "at_coords_call", [Register], []),
+    op!(0x8D, "at_coords_talk", [Register], []),
+    op!(0x8E, "col_npcin", [Register], []),
+    op!(0x8F, "col_npcinr", [Register], []),
+    op!(0x90, "switch_on", [], [I32]),
+    op!(0x91, "switch_off", [], [I32]),
+    op!(0x92, "playbgm_epi", [], [I32]),
+    op!(0x93, "set_mainwarp", [], [I32]),
+    op!(0x94, "set_obj_param", [Register, Register], []),
+    op!(0x95, "set_floor_handler", [], [I32, Label]),
+    op!(0x96, "clr_floor_handler", [], [I32]),
+    op!(0x97, "col_plinaw", [Register], []),
+    op!(0x98, "hud_hide", [], []),
+    op!(0x99, "hud_show", [], []),
+    op!(0x9A, "cine_enable", [], []),
+    op!(0x9B, "cine_disable", [], []),
+    op!(0xA1, "set_qt_failure", [Label], []),
+    op!(0xA2, "set_qt_success", [Label], []),
+    op!(0xA3, "clr_qt_failure", [], []),
+    op!(0xA4, "clr_qt_success", [], []),
+    op!(0xA5, "set_qt_cancel", [Label], []),
+    op!(0xA6, "clr_qt_cancel", [], []),
+    op!(0xA8, "pl_walk", [], [Register]),
+    op!(0xB0, "pl_add_meseta", [], [I32, I32]),
+    op!(0xB1, "thread_stg", [Label], []),
+    op!(0xB2, "del_obj_param", [Register], []),
+    op!(0xB3, "item_create", [Register, Register], []),
+    op!(0xB4, "item_create2", [Register, Register], []),
+    op!(0xB5, "item_delete", [Register, Register], []),
+    op!(0xB6, "item_delete2", [Register, Register], []),
+    op!(0xB7, "item_check", [Register, Register], []),
+    op!(0xB8, "setevt", [], [I32]),
+    op!(0xB9, "get_difflvl", [Register], []),
+    op!(0xBA, "set_qt_exit", [Label], []),
+    op!(0xBB, "clr_qt_exit", [], []),
+    op!(0xC0, "particle", [], [Register]),
+    op!(0xC1, "npc_text", [], [I32, String]),
+    op!(0xC2, "npc_chkwarp", [], []),
+    op!(0xC3, "pl_pkoff", [], []),
+    op!(0xC4, "map_designate", [Register], []),
+    op!(0xC5, "masterkey_on", [], []),
+    op!(0xC6, "masterkey_off", [], []),
+    op!(0xC7, "window_time", [], []),
+    op!(0xC8, "winend_time", [], []),
+    op!(0xC9, "winset_time", [Register], []),
+    op!(0xCA, "getmtime", [Register], []),
+    op!(0xCB, "set_quest_board_handler", [], [I32, Label, String]),
+    op!(0xCC, "clear_quest_board_handler", [], [I32]),
+    op!(0xCD, "particle_id", [], [Register]),
+    op!(0xCE, "npc_crptalk_id", [], [Register]),
+    op!(0xCF, "npc_lang_clean", [], []),
+    op!(0xD0, "pl_pkon", [], []),
+    op!(0xD1, "pl_chk_item2", [Register, Register], []),
+    op!(0xD2, "enable_mainmenu", [], []),
+    op!(0xD3, "disable_mainmenu", [], []),
+    op!(0xD4, "start_battlebgm", [], []),
+    op!(0xD5, "end_battlebgm", [], []),
+    op!(0xD6, "disp_msg_qb", [], [String]),
+    op!(0xD7, "close_msg_qb", [], []),
+    op!(0xD8, "set_eventflag", [], [I32, I32]),
+    op!(0xD9, "sync_leti", [], [Register, I32]),
+    op!(0xDA, "set_returnhunter", [], []),
+    op!(0xDB, "set_returncity", [], []),
+    op!(0xDC, "load_pvr", [], []),
+    op!(0xDD, "load_midi", [], []),
+    op!(0xDF, "npc_param", [], [Register, I32]),
+    op!(0xE0, "pad_dragon", [], []),
+    op!(0xE1, "clear_mainwarp", [], [I32]),
+    op!(0xE2, "pcam_param", [], [Register]),
+    op!(0xE3, "start_setevt", [], [Register, I32]),
+    op!(0xE4, "warp_on", [], []),
+    op!(0xE5, "warp_off", [], []),
+    op!(0xE6, "get_slotnumber", [Register], []),
+    op!(0xE7, "get_servernumber", [Register], []),
+    op!(0xE8, "set_eventflag2", [], [I32, Register]),
+    op!(0xE9, "res", [Register, Register], []),
+    op!(0xEB, "enable_bgmctrl", [], [I32]),
+    op!(0xEC, "sw_send", [Register], []),
+    op!(0xED, "create_bgmctrl", [], []),
+    op!(0xEE, "pl_add_meseta2", [], [I32]),
+    op!(0xEF, "sync_let", [], [Register, Register]),
+    op!(0xF0, "send_regwork", [], [Register, Register]),
+    op!(0xF1, "leti_fixed_camera", [Register], []),
+    op!(0xF2, "default_camera_pos1", [], []),
+    op!(0xF801, "set_chat_callback", [], [Register, String]),
+    op!(0xF808, "get_difflvl2", [Register], []),
+    op!(0xF809, "get_number_of_player1", [Register], []),
+    op!(0xF80A, "get_coord_of_player", [Register, Register], []),
+    op!(0xF80B, "enable_map", [], []),
+    op!(0xF80C, "disable_map", [], []),
+    op!(0xF80D, "map_designate_ex", [Register], []),
+    op!(0xF80E, "disable_weapon_drop", [], [I32]),
+    op!(0xF80F, "enable_weapon_drop", [], [I32]),
+    op!(0xF810, "ba_initial_floor", [], [I32]),
+    op!(0xF811, "set_ba_rules", [], []),
+    op!(0xF812, "ba_set_tech", [], [I32]),
+    op!(0xF813, "ba_set_equip", [], [I32]),
+    op!(0xF814, "ba_set_mag", [], [I32]),
+    op!(0xF815, "ba_set_item", [], [I32]),
+    op!(0xF816, "ba_set_trapmenu", [], [I32]),
+    op!(0xF818, "ba_set_respawn", [], [I32]),
+    op!(0xF819, "ba_set_charge", [], [I32]),
+    op!(0xF81A, "ba_dropwep", [], [I32]),
+    op!(0xF81B, "ba_teams", [], [I32]),
+    op!(0xF81C, "ba_start", [], []),
+    op!(0xF81D, "death_lvl_up", [], [I32]),
+    op!(0xF81E, "ba_set_meseta", [], [I32]),
+    op!(0xF820, "cmode_stage", [], [I32]),
+    op!(0xF821, "nop_f821", [], [Register]),
+    op!(0xF822, "nop_f822", [Register], []),
+    op!(0xF823, "set_cmode_char_template", [], [I32]),
+    op!(0xF824, "set_cmode_diff", [], [I32]),
+    op!(0xF825, "exp_multiplication", [Register], []),
+    op!(0xF826, "if_player_alive_cm", [Register], []),
+    op!(0xF827, "get_user_is_dead", [Register], []),
+    op!(0xF828, "go_floor", [Register, Register], []),
+    op!(0xF82B, "unlock_door2", [], [I32, I32]),
+    op!(0xF82C, "lock_door2", [], [I32, I32]),
+    op!(0xF82D, "if_switch_not_pressed", [Register], []),
+    op!(0xF82E, "if_switch_pressed", [Register], []),
+    op!(0xF830, "control_dragon", [Register], []),
+    op!(0xF831, "release_dragon", [], []),
+    op!(0xF838, "shrink", [Register], []),
+    op!(0xF839, "unshrink", [Register], []),
+    op!(0xF83C, "display_clock2", [Register], []),
+    op!(0xF83D, "set_area_total", [], [I32]),
+    op!(0xF83E, "delete_area_title", [], [I32]),
+    op!(0xF840, "load_npc_data", [], []),
+    op!(0xF841, "get_npc_data", [DataLabel], []),
+    op!(0xF848, "give_damage_score", [Register], []),
+    op!(0xF849, "take_damage_score", [Register], []),
+    op!(0xF84A, "unk_score_f84a", [Register], []),
+    op!(0xF84B, "unk_score_f84b", [Register], []),
+    op!(0xF84C, "kill_score", [Register], []),
+    op!(0xF84D, "death_score", [Register], []),
+    op!(0xF84E, "unk_score_f84e", [Register], []),
+    op!(0xF84F, "enemy_death_score", [Register], []),
+    op!(0xF850, "meseta_score", [Register], []),
+    op!(0xF851, "ba_set_trap_count", [Register], []),
+    op!(0xF852, "ba_set_target", [], [I32]),
+    op!(0xF853, "reverse_warps", [], []),
+    op!(0xF854, "unreverse_warps", [], []),
+    op!(0xF855, "set_ult_map", [], []),
+    op!(0xF856, "unset_ult_map", [], []),
+    op!(0xF857, "set_area_title", [], [String]),
+    op!(0xF858, "bgm_ctrl_sound", [], [I32]),
+    op!(0xF85A, "equip_item", [Register], []),
+    op!(0xF85B, "unequip_item", [], [I32, I32]),
+    op!(0xF85E, "ba_disable_weapon_drop", [], [I32]),
+    op!(0xF85F, "ba_enable_weapon_drop", [], [I32]),
+    op!(0xF860, "clear_score_announce", [], []),
+    op!(0xF861, "set_score_announce", [], [I32]),
+    op!(0xF862, "give_s_rank_weapon", [], [Register, Register, String]),
+    op!(0xF863, "get_mag_levels", [Register], []),
+    op!(0xF864, "set_cmode_rank", [], [I32, String]),
+    op!(0xF865, "award_item_name", [], []),
+    op!(0xF866, "award_item_select", [], []),
+    op!(0xF867, "award_item_give_to", [Register], []),
+    op!(0xF868, "set_cmode_rank_result", [Register, Register], []),
+    op!(0xF869, "check_rank_time", [Register, Register], []),
+    op!(0xF86A, "item_create_cmode", [Register, Register], []),
+    op!(0xF86B, "ba_box_drops", [Register], []),
+    op!(0xF86C, "award_item_ok", [Register], []),
+    op!(0xF86D, "ba_set_trapself", [], []),
+    op!(0xF86E, "ba_clear_trapself", [], []),
+    op!(0xF86F, "ba_set_lives", [], [I32]),
+    op!(0xF870, "ba_set_tech_lvl", [], [I32]),
+    op!(0xF871, "ba_set_lvl", [], [I32]),
+    op!(0xF872, "ba_set_time_limit", [], [I32]),
+    op!(0xF873, "boss_is_dead", [Register], []),
+    op!(0xF877, "enable_techs", [], [I32]),
+    op!(0xF878, "disable_techs", [], [I32]),
+    op!(0xF879, "get_gender", [Register, Register], []),
+    op!(0xF87A, "get_chara_class", [Register, Register], []),
+    op!(0xF87B, "take_slot_meseta", [Register, Register], []),
+    op!(0xF87C, "get_guild_card_file_creation_time", [Register], []),
+    op!(0xF87D, "kill_player", [Register], []),
+    op!(0xF87E, "get_serial_number", [Register], []),
+    op!(0xF87F, "get_eventflag", [Register, Register], []),
+    op!(0xF880, "set_trap_damage", [Register], []),
+    op!(0xF881, "get_pl_name", [Register], []),
+    op!(0xF882, "get_pl_job", [Register], []),
+    op!(0xF883, "get_player_status", [Register, Register], []),
+    op!(0xF884, "set_player_status", [Register, Register], []),
+    op!(0xF885, "send_to_server", [Register], []),
+    op!(0xF886, "get_server_response", [Register], []),
+    op!(0xF887, "set_shrink_size", [Register, Register], []),
+    op!(0xF888, "ba_close_msg", [], []),
+    op!(0xF889, "ba_set_box_drop_area", [], [I32]),
+    op!(0xF88A, "get_player_alive", [Register, Register], []),
+    op!(0xF88B, "set_pkfd_mode", [], []),
+    op!(0xF88C, "get_ba_record", [Register, Register], []),
+    op!(0xF88D, "set_window_time", [Register], []),
+    op!(0xF88E, "hide_clock", [], []),
+    op!(0xF88F, "set_cmode_grave_rates", [Register], []),
+    op!(0xF890, "clear_mainwarp_all", [], []),
+    op!(0xF891, "load_enemy_data", [], [I32]),
+    op!(0xF892, "get_physical_data", [DataLabel], []),
+    op!(0xF893, "get_attack_data", [DataLabel], []),
+    op!(0xF894, "get_resist_data", [DataLabel], []),
+    op!(0xF895, "get_movement_data", [DataLabel], []),
+    op!(0xF898, "shift_left", [Register, Register], []),
+    op!(0xF899, "shift_right", [Register, Register], []),
+    op!(0xF89A, "get_random", [Register, Register], []),
+    op!(0xF89B, "reset_map", [], []),
+    op!(0xF89C, "disp_chl_retry_menu", [Register], []),
+    op!(0xF89D, "chl_reverser", [], []),
+    op!(0xF89E, "ba_forbid_scape_dolls", [], [I32]),
+    op!(0xF89F, "player_recovery", [Register], []),
+    op!(0xF8A0, "disable_bosswarp_option", [], []),
+    op!(0xF8A1, "enable_bosswarp_option", [], []),
+    op!(0xF8A2, "is_bosswarp_opt_disabled", [Register], []),
+    op!(0xF8A3, "load_serial_number_to_flag_buf", [], []),
+    op!(0xF8A4, "write_flag_buf_to_event_flags", [Register], []),
+    op!(0xF8A5, "set_chat_callback_no_filter", [], [Register, String]),
+    op!(0xF8A6, "set_symbol_chat_collision", [Register], []),
+    op!(0xF8A7, "set_shrink_size2", [], [I32, Register]),
+    op!(0xF8A8, "death_tech_lvl_up2", [], [I32]),
+    op!(0xF8A9, "vol_opt_is_disabled", [Register], []),
+    op!(0xF8AA, "is_there_grave_message", [Register], []),
+    op!(0xF8AB, "get_ba_record2", [Register], []),
+    op!(0xF8AC, "get_score", [Register, Register], []),
+    op!(0xF8AD, "enable_bgm_ctrl", [], [I32]),
+    op!(0xF8AE, "disable_bgm_ctrl", [], [I32]),
+    op!(0xF8AF, "set_game_timer", [Register], []),
+    op!(0xF8B0, "disp_ba_record_menu", [Register], []),
+    op!(0xF8B1, "disp_chl_rank_menu", [Register], []),
+    op!(0xF8B2, "set_item_drop_trap", [Register], []),
+    op!(0xF8B3, "turn_off_bgm_p2", [], []),
+    op!(0xF8B4, "turn_on_bgm_p2", [], []),
+    op!(0xF8B5, "load_unk_data", [], [I32, I32, I32, I32, Register, DataLabel]),
+    op!(0xF8B6, "particle2", [], [Register, I32, I32]),
+    op!(0xF8B9, "get_ba_score", [Register], []),
+    op!(0xF8BA, "get_ba_record3", [Register], []),
+    op!(0xF8BC, "set_episode", [U32], []),
+    op!(0xF8C0, "file_dl_req", [], [I32, String]),
+    op!(0xF8C1, "get_dl_status", [Register], []),
+    op!(0xF8C2, "gba_unknown4", [], []),
+    op!(0xF8C3, "get_gba_state", [Register], []),
+    op!(0xF8C4, "congrats_msg_multi_cm", [Register], []),
+    op!(0xF8C5, "stage_end_multi_cm", [Register], []),
+    op!(0xF8C6, "qexit", [], []),
+    op!(0xF8C7, "use_animation", [Register, Register], []),
+    op!(0xF8C8, "stop_animation", [Register], []),
+    op!(0xF8C9, "run_to_coord", [Register, Register], []),
+    op!(0xF8CA, "set_slot_invincible", [Register, Register], []),
+    op!(0xF8CB, "clear_slot_invincible", [Register], []),
+    op!(0xF8CC, "set_slot_poison", [Register], []),
+    op!(0xF8CD, "set_slot_paralyze", [Register], []),
+    op!(0xF8CE, "set_slot_shock", [Register], []),
+    op!(0xF8CF, "set_slot_freeze", [Register], []),
+    op!(0xF8D0, "set_slot_slow", [Register], []),
+    op!(0xF8D1, "set_slot_confuse", [Register], []),
+    op!(0xF8D2, "set_slot_shifta", [Register], []),
+    op!(0xF8D3, "set_slot_deband", [Register], []),
+    op!(0xF8D4, "set_slot_jellen", [Register], []),
+    op!(0xF8D5, "set_slot_zalure", [Register], []),
+    op!(0xF8D6, "fleti_fixed_camera", [Register], []),
+    op!(0xF8D7, "fleti_locked_camera", [], [I32, Register]),
+    op!(0xF8D8, "default_camera_pos2", [], []),
+    op!(0xF8D9, "set_motion_blur", [], []),
+    op!(0xF8DA, "set_screen_bw", [], []),
+    op!(0xF8DB, "get_vector_from_path", [], [I32, I32, I32, I32, Register, Label]),
+    op!(0xF8DC, "npc_action_string", [Register, Register, DataLabel], []),
+    op!(0xF8DD, "get_pad_cond", [Register, Register], []),
+    op!(0xF8DE, "get_button_cond", [Register, Register], []),
+    op!(0xF8DF, "freeze_enemies", [], []),
+    op!(0xF8E0, "unfreeze_enemies", [], []),
+    op!(0xF8E1, "freeze_everything", [], []),
+    op!(0xF8E2, "unfreeze_everything", [], []),
+    op!(0xF8E3, "restore_hp", [Register], []),
+    op!(0xF8E4, "restore_tp", [Register], []),
+    op!(0xF8E5, "close_chat_bubble", [Register], []),
+    op!(0xF8E6, "move_coords_object", [Register, Register], []),
+    op!(0xF8E7, "at_coords_call_ex", [Register, Register], []),
+    op!(0xF8E8, "at_coords_talk_ex", [Register, Register], []),
+    op!(0xF8E9, "walk_to_coord_call_ex", [Register, Register], []),
+    op!(0xF8EA, "col_npcinr_ex", [Register, Register], []),
+    op!(0xF8EB, "set_obj_param_ex", [Register, Register], []),
+    op!(0xF8EC, "col_plinaw_ex", [Register, Register], []),
+    op!(0xF8ED, "animation_check", [Register, Register], []),
+    op!(0xF8EE, "call_image_data", [], [I32, DataLabel]),
+    op!(0xF8F0, "turn_off_bgm_p3", [], []),
+    op!(0xF8F1, "turn_on_bgm_p3", [], []),
+    op!(0xF8F2, "load_bgm", [], [I32]),
+    op!(0xF8F3, "particle3", [], [Register, I32, I32, I32]),
+    op!(0xF8F4, "give_or_take_card", [], [Register, I32]),
+    op!(0xF8F5, "get_time_played", [Register], []),
+    op!(0xF8F6, "get_guildcard_total", [Register], []),
+    op!(0xF8F7, "get_slot_meseta", [Register], []),
+    op!(0xF8F8, "get_player_level", [], [I32, Register]),
+    op!(0xF8F9, "get_section_id", [], [I32, Register]),
+    op!(0xF8FA, "get_player_hp", [], [Register, Register]),
+    op!(0xF8FB, "get_floor_number", [], [Register, Register]),
+    op!(0xF8FC, "get_coord_player_detect", [Register, Register], []),
+    op!(0xF8FD, "read_global_flag", [], [I32, Register]),
+    op!(0xF8FE, "write_global_flag", [], [I32, I32]),
+    op!(0xF8FF, "item_detect_bank2", [Register, Register], []),
+    op!(0xF901, "dec2float", [Register, Register], []),
+    op!(0xF902, "float2dec", [Register, Register], []),
+    op!(0xF903, "flet", [Register, Register], []),
+    op!(0xF904, "fleti", [Register, F32], []),
+    op!(0xF908, "fadd", [Register, Register], []),
+    op!(0xF909, "faddi", [Register, F32], []),
+    op!(0xF90A, "fsub", [Register, Register], []),
+    op!(0xF90B, "fsubi", [Register, F32], []),
+    op!(0xF90C, "fmul", [Register, Register], []),
+    op!(0xF90D, "fmuli", [Register, F32], []),
+    op!(0xF90E, "fdiv", [Register, Register], []),
+    op!(0xF90F, "fdivi", [Register, F32], []),
+    op!(0xF910, "get_total_deaths", [], [I32, Register]),
+    op!(0xF911, "get_stackable_item_count", [Register, Register], []),
+    op!(0xF912, "freeze_and_hide_equip", [], []),
+    op!(0xF913, "thaw_and_show_equip", [], []),
+    op!(0xF914, "set_palettex_callback", [], [I32, Label]),
+    op!(0xF915, "activate_palettex", [], [I32]),
+    op!(0xF916, "enable_palettex", [], [I32]),
+    op!(0xF917, "restore_palettex", [], [I32]),
+    op!(0xF918, "disable_palettex", [], [I32]),
+    op!(0xF919, "get_palettex_activated", [], [I32, Register]),
+    op!(0xF91A, "get_unknown_palettex_status", [], [I32, I32, Register]),
+    op!(0xF91B, "disable_movement2", [], [I32]),
+    op!(0xF91C, "enable_movement2", [], [I32]),
+    op!(0xF91D, "get_time_played2", [Register], []),
+    op!(0xF91E, "get_guildcard_total2", [Register], []),
+    op!(0xF91F, "get_slot_meseta2", [Register], []),
+    op!(0xF920, "get_player_level2", [], [I32, Register]),
+    op!(0xF921, "get_section_id2", [], [I32, Register]),
+    op!(0xF922, "get_player_hp2", [], [Register, Register]),
+    op!(0xF923, "get_floor_number2", [], [Register, Register]),
+    op!(0xF924, "get_coord_player_detect2", [Register, Register], []),
+    op!(0xF925, "read_global_flag2", [], [I32, Register]),
+    op!(0xF926, "write_global_flag2", [], [I32, I32]),
+    op!(0xF927, "item_detect_bank", [Register, Register], []),
+    op!(0xF928, "floor_player_detect", [Register], []),
+    op!(0xF929, "read_disk_file", [], [String]),
+    op!(0xF92A, "open_pack_select", [], []),
+    op!(0xF92B, "item_select", [Register], []),
+    op!(0xF92C, "get_item_id", [Register], []),
+    op!(0xF92D, "color_change", [], [I32, I32, I32, I32, I32]),
+    op!(0xF92E, "send_statistic", [], [I32, I32, I32, I32, I32, I32, I32, I32]),
+    op!(0xF92F, "gba_write_identifiers", [], [I32, I32]),
+    op!(0xF930, "chat_box", [], [I32, I32, I32, I32, I32, String]),
+    op!(0xF931, "chat_bubble", [], [I32, String]),
+    op!(0xF932, "set_episode2", [Register], []),
+    op!(0xF933, "item_create_multi_cm", [Register], []),
+    op!(0xF934, "scroll_text", [], [I32, I32, I32, I32, I32, I32, Register, String]),
+    op!(0xF935, "gba_create_dl_graph", [], []),
+    op!(0xF936, "gba_destroy_dl_graph", [], []),
+    op!(0xF937, "gba_update_dl_graph", [], []),
+    op!(0xF938, "add_damage_to", [], [I32, I32]),
+    op!(0xF939, "item_delete3", [], [I32]),
+    op!(0xF93A, "get_item_info", [], [I32, Register]),
+    op!(0xF93B, "item_packing1", [], [I32]),
+    op!(0xF93C, "item_packing2", [], [I32, I32]),
+    op!(0xF93D, "get_lang_setting", [], [Register]),
+    op!(0xF93E, "prepare_statistic", [], [I32, Label, Label]),
+    op!(0xF93F, "keyword_detect", [], []),
+    op!(0xF940, "keyword", [], [Register, I32, String]),
+    op!(0xF941, "get_guildcard_num", [], [I32, Register]),
+    op!(0xF942, "get_recent_symbol_chat", [], [I32, Register]),
+    op!(0xF943, "create_symbol_chat_capture_buffer", [], []),
+    op!(0xF944, "get_item_stackability", [], [I32, Register]),
+    op!(0xF945, "initial_floor", [], [I32]),
+    op!(0xF946, "sin", [], [Register, I32]),
+    op!(0xF947, "cos", [], [Register, I32]),
+    op!(0xF948, "tan", [], [Register, I32]),
+    op!(0xF949, "atan2_int", [], [Register, I32, I32]),
+    op!(0xF94A, "olga_flow_is_dead", [Register], []),
+    op!(0xF94B, "particle_effect_nc", [Register], []),
+    op!(0xF94C, "player_effect_nc", [Register], []),
+    op!(0xF94D, "give_or_take_rare_item", [], [I32, I32, I32]),
+    op!(0xF94E, "enable_sonar", [], []),
+    op!(0xF94F, "disable_sonar", [], []),
+    op!(0xF950, "bb_p2_menu", [], [I32]),
+    op!(0xF951, "bb_map_designate", [U8, U16, U8, U8], []),
+    op!(0xF952, "bb_get_number_in_pack", [Register], []),
+    op!(0xF953, "bb_swap_item", [], [I32, I32, I32, I32, I32, I32, Label, Label]),
+    op!(0xF954, "bb_check_wrap", [], [I32, Register]),
+    op!(0xF955, "bb_exchange_pd_item", [], [I32, I32, I32, Label, Label]),
+    op!(0xF956, "bb_exchange_pd_srank", [], [I32, I32, I32, I32, I32, Label, Label]),
+    op!(0xF957, "bb_exchange_pd_special", [], [I32, I32, I32, I32, I32, I32, Label, Label]),
+    op!(0xF958, "bb_exchange_pd_percent", [], [I32, I32, I32, I32, I32, I32, Label, Label]),
+    op!(0xF959, "bb_set_ep4_boss_can_escape", [], [I32]),
+    op!(0xF95A, "bb_is_ep4_boss_dying", [Register], []),
+    op!(0xF95B, "bb_send_6xd9", [], [I32, I32, I32, I32, Label, Label]),
+    op!(0xF95C, "bb_exchange_slt", [], [I32, I32, I32, I32]),
+    op!(0xF95D, "bb_exchange_pc", [], []),
+    op!(0xF95E, "bb_box_create_bp", [], [I32, I32, I32]),
+    op!(0xF95F, "bb_exchange_pt", [], [I32, I32, I32, I32, I32]),
+    op!(0xF960, "bb_send_6xe2", [], [I32]),
+    op!(0xF961, "bb_get_6xe3_status", [Register], [])
+];
@@ -0,0 +1,164 @@
+use std::collections::HashMap;
+use std::fmt;
+use script::{self, Arg, Script, SegmentData};
+use types::{Language, Version};
+
+// A translatable string of a script. IDs stay the same as long as the code of the script doesn't change.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct Text {
+    pub id: String,
+    pub text: String
+}
+
+#[derive(Debug)]
+pub struct TextError {
+    pub id: String,
+    pub message: &'static str
+}
+
+impl fmt::Display for TextError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}: {}", self.id, self.message)
+    }
+}
+
+const QUEST_NAME: &'static str = "quest_name";
+const SHORT_DESCRIPTION: &'static str = "short_description";
+const LONG_DESCRIPTION: &'static str = "long_description";
+
+// Returns the header fields and every string in the object code.
+// The header fields are identified by their name. Strings in the object code are identified by the lowest label of
+// their segment, or "start" for code before the first label, followed by a colon and the index of the instruction
+// within the segment. String segments are identified by their label alone.
+pub fn export(script: &Script) -> Vec<Text> {
+    let mut texts = vec![
+        Text { id: QUEST_NAME.to_string(), text: script.quest_name.clone() },
+        Text { id: SHORT_DESCRIPTION.to_string(), text: script.short_description.clone() },
+        Text { id: LONG_DESCRIPTION.to_string(), text: script.long_description.clone() }
+    ];
+
+    for segment in &script.segments {
+        let segment_id = segment.id();
+
+        match segment.data {
+            SegmentData::Instructions(ref instructions) => {
+                for (index, instruction) in instructions.iter().enumerate() {
+                    for arg in &instruction.args {
+                        if let Arg::String(ref string) = *arg {
+                            texts.push(Text { id: format!("{}:{}", segment_id, index), text: string.clone() });
+                        }
+                    }
+                }
+            },
+            SegmentData::String(ref string) => texts.push(Text { id: segment_id, text: string.clone() }),
+            SegmentData::Data(_) => {}
+        }
+    }
+
+    return texts;
+}
+
+// Replaces the strings with the given IDs, strings that aren't in texts are kept. The segments are written back with
+// new offsets, so changing the length of a string moves the labels after it.
+// Header data that overlaps a header field that changed is dropped, it would overwrite the new string.
+pub fn import(script: &mut Script, texts: &[Text]) -> Result<(), TextError> {
+    let mut texts_by_id: HashMap<&str, &str> = HashMap::new();
+
+    for text in texts {
+        if texts_by_id.insert(&text.id, &text.text).is_some() {
+            return Err(TextError { id: text.id.clone(), message: "the ID occurs more than once" });
+        }
+    }
+
+    for &(id, start, end) in &header_fields(script.version) {
+        let field = match id {
+            QUEST_NAME => &mut script.quest_name,
+            SHORT_DESCRIPTION => &mut script.short_description,
+            _ => &mut script.long_description
+        };
+
+        if let Some(text) = texts_by_id.remove(id) {
+            if *field != text {
+                *field = text.to_string();
+                remove_header_data(&mut script.header_data, start, end);
+            }
+        }
+    }
+
+    for segment in &mut script.segments {
+        let segment_id = segment.id();
+
+        match segment.data {
+            SegmentData::Instructions(ref mut instructions) => {
+                for (index, instruction) in instructions.iter_mut().enumerate() {
+                    for arg in &mut instruction.args {
+                        if let Arg::String(ref mut string) = *arg {
+                            if let Some(text) = texts_by_id.remove(&format!("{}:{}", segment_id, index)[..]) {
+                                *string = text.to_string();
+                            }
+                        }
+                    }
+                }
+            },
+            SegmentData::String(ref mut string) => if let Some(text) = texts_by_id.remove(&segment_id[..]) {
+                *string = text.to_string();
+            },
+            SegmentData::Data(_) => {}
+        }
+    }
+
+    if let Some(id) = texts_by_id.keys().min() {
+        return Err(TextError { id: id.to_string(), message: "the script has no string with this ID" });
+    }
+
+    return Ok(());
+}
+
+// Changes the language of a script, which changes the encoding of Dreamcast and GameCube scripts. Header data of the
+// header fields is dropped when the encoding changes.
+pub fn set_language(script: &mut Script, language: Language) {
+    let text_encoding = script.text_encoding();
+    script.language = language;
+
+    if script.text_encoding() != text_encoding {
+        for &(_, start, end) in &header_fields(script.version) {
+            remove_header_data(&mut script.header_data, start, end);
+        }
+    }
+}
+
+// IDs and byte ranges of the header fields.
+fn header_fields(version: Version) -> [(&'static str, usize, usize); 3] {
+    let (name_size, short_description_size, long_description_size) = script::string_field_sizes(version);
+    let name_offset = script::string_fields_offset(version);
+    let short_description_offset = name_offset + name_size;
+    let long_description_offset = short_description_offset + short_description_size;
+    return [
+        (QUEST_NAME, name_offset, short_description_offset),
+        (SHORT_DESCRIPTION, short_description_offset, long_description_offset),
+        (LONG_DESCRIPTION, long_description_offset, long_description_offset + long_description_size)
+    ];
+}
+
+// Drops header data from start to end, header data around it is kept.
+fn remove_header_data(header_data: &mut Vec<(usize, Vec<u8>)>, start: usize, end: usize) {
+    let mut result = Vec::with_capacity(header_data.len());
+
+    for (offset, bytes) in header_data.drain(..) {
+        let bytes_end = offset + bytes.len();
+
+        if bytes_end <= start || offset >= end {
+            result.push((offset, bytes));
+            continue;
+        }
+
+        if offset < start {
+            result.push((offset, bytes[0..(start - offset)].to_vec()));
+        }
+        if bytes_end > end {
+            result.push((end, bytes[(end - offset)..].to_vec()));
+        }
+    }
+
+    *header_data = result;
+}
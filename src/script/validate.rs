@@ -0,0 +1,296 @@
+// Static checks of the script of a .bin file.
+use std::collections::{BTreeMap, HashSet};
+use std::fmt;
+use std::io::Cursor;
+use byteorder::{ByteOrder, LittleEndian};
+use read::{self, ReadError};
+use read::bin;
+use script::{Instruction, VA_CALL};
+use script::code;
+use script::opcodes::{self, Param};
+use types::TextMode;
+use util::{TextDecoder, TextEncoding};
+
+// A problem at offset, which is relative to the start of the .bin file. Label is the lowest label of the function
+// the offset is in, None for code before the first label.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct Problem {
+    pub offset: usize,
+    pub label: Option<u16>,
+    pub kind: ProblemKind
+}
+
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum ProblemKind {
+    UnknownOpcode(u16),
+    // The instruction continues past the end of its function.
+    TruncatedInstruction,
+    // A float that isn't finite or a string that can't be decoded.
+    InvalidArgument { mnemonic: &'static str },
+    // The label isn't in the function offset table or its entry is -1.
+    UndefinedLabel { mnemonic: &'static str, label: u32 },
+    // The function offset table entry of the label points outside the object code.
+    LabelPastEnd { label: u16, offset: i32 },
+    // The function isn't reachable from label 0 or the entry labels. Only a warning, functions can be referenced
+    // in ways that aren't known, e.g. by objects or events in the .dat file.
+    Unreachable,
+    // The number of arguments pushed with the arg_push opcodes doesn't match the opcode's stack parameters.
+    ArgumentCount { mnemonic: &'static str, expected: usize, pushed: usize }
+}
+
+impl ProblemKind {
+    // Warnings point at code that may be unused, the other problems make the script invalid.
+    pub fn is_warning(&self) -> bool {
+        *self == ProblemKind::Unreachable
+    }
+}
+
+impl fmt::Display for ProblemKind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            ProblemKind::UnknownOpcode(code) => write!(f, "unknown opcode {:#x}", code),
+            ProblemKind::TruncatedInstruction => write!(f, "instruction continues past the end of the function"),
+            ProblemKind::InvalidArgument { mnemonic } => write!(f, "invalid argument for {}", mnemonic),
+            ProblemKind::UndefinedLabel { mnemonic, label } => write!(f, "{} references undefined label {}", mnemonic,
+                                                                        label),
+            ProblemKind::LabelPastEnd { label, offset } =>
+                write!(f, "label {} points to offset {}, outside the object code", label, offset),
+            ProblemKind::Unreachable => write!(f, "function is unreachable"),
+            ProblemKind::ArgumentCount { mnemonic, expected, pushed } =>
+                write!(f, "{} expects {} pushed arguments, got {}", mnemonic, expected, pushed)
+        }
+    }
+}
+
+// A function of the object code, from one offset in the function offset table to the next.
+struct Function {
+    start: usize,
+    labels: Vec<u16>,
+    // Instructions with their offset in the object code, up to the first invalid instruction.
+    instructions: Vec<(usize, Instruction)>,
+    // The problem that stopped decoding.
+    problem: Option<(usize, ProblemKind)>
+}
+
+impl Function {
+    fn label(&self) -> Option<u16> {
+        self.labels.iter().min().cloned()
+    }
+
+    // Whether execution continues in the next function after the last instruction.
+    fn falls_through(&self) -> bool {
+        self.problem.is_none() && match self.instructions.last() {
+            Some(&(_, ref instruction)) => instruction.falls_through(),
+            None => true
+        }
+    }
+}
+
+// Checks the script of a decompressed .bin file and returns its problems ordered by offset.
+// Entry_labels are the labels the game runs besides label 0, e.g. the script labels of the NPCs in the .dat file.
+// Functions that are referenced as data aren't checked. Arguments pushed with the arg_push opcodes are matched with
+// the stack parameters of the next instruction that isn't an arg_push, labels that are pushed count as references.
+pub fn validate(data: &[u8], entry_labels: &[u16]) -> read::Result<Vec<Problem>> {
+    let version = bin::detect_version(data).ok_or(ReadError::InvalidData)?;
+    let bin_file = bin::read(&mut Cursor::new(data), version, &mut TextDecoder::new(TextMode::Lenient))?;
+    let object_code_offset = bin_file.object_code_offset as usize;
+    let function_offset_table_offset = bin_file.function_offset_table_offset as usize;
+
+    if function_offset_table_offset < object_code_offset || function_offset_table_offset > data.len() {
+        return Err(ReadError::InvalidData);
+    }
+
+    let object_code = &data[object_code_offset..function_offset_table_offset];
+    let function_offsets: Vec<i32> = data[function_offset_table_offset..]
+        .chunks(4)
+        .filter(|chunk| chunk.len() == 4)
+        .map(LittleEndian::read_i32)
+        .collect();
+    let text_encoding = TextEncoding::for_quest(version, bin_file.language);
+    let mut problems = Vec::new();
+    let mut labels_by_offset: BTreeMap<usize, Vec<u16>> = BTreeMap::new();
+
+    for (label, &offset) in function_offsets.iter().enumerate() {
+        if offset == -1 {
+            continue;
+        }
+
+        if offset < 0 || offset as usize > object_code.len() {
+            // Reported at the entry in the function offset table.
+            let entry_offset = function_offset_table_offset + 4 * label - object_code_offset;
+            problems.push((entry_offset, Some(label as u16), ProblemKind::LabelPastEnd {
+                label: label as u16,
+                offset: offset
+            }));
+            continue;
+        }
+
+        labels_by_offset.entry(offset as usize).or_insert_with(Vec::new).push(label as u16);
+    }
+
+    if object_code.len() > 0 {
+        labels_by_offset.entry(0).or_insert_with(Vec::new);
+    }
+
+    let offsets: Vec<usize> = labels_by_offset.keys().cloned().collect();
+    let functions: Vec<Function> = offsets.iter()
+        .enumerate()
+        .map(|(index, &start)| {
+            let end = offsets.get(index + 1).cloned().unwrap_or(object_code.len());
+            read_function(object_code, start, end, labels_by_offset[&start].clone(), text_encoding)
+        })
+        .collect();
+    let defined = |label: u32| match function_offsets.get(label as usize) {
+        Some(&offset) => offset >= 0 && offset as usize <= object_code.len(),
+        None => false
+    };
+
+    // Label references per function, as indices into functions.
+    let mut references: Vec<Vec<usize>> = vec![Vec::new(); functions.len()];
+    let mut data_labels = HashSet::new();
+
+    for (index, function) in functions.iter().enumerate() {
+        let mut pushed: Vec<&Instruction> = Vec::new();
+
+        for &(offset, ref instruction) in &function.instructions {
+            if opcodes::is_arg_push(instruction.opcode) {
+                pushed.push(instruction);
+                continue;
+            }
+
+            // Va_call takes the arguments pushed after va_start, however many there are.
+            if instruction.opcode.code != VA_CALL && pushed.len() != instruction.opcode.stack_params.len() {
+                problems.push((offset, function.label(), ProblemKind::ArgumentCount {
+                    mnemonic: instruction.opcode.mnemonic,
+                    expected: instruction.opcode.stack_params.len(),
+                    pushed: pushed.len()
+                }));
+            }
+
+            let labels = instruction.referenced_labels(&pushed);
+            pushed.clear();
+
+            for (param, label) in labels {
+                if !defined(label) {
+                    problems.push((offset, function.label(), ProblemKind::UndefinedLabel {
+                        mnemonic: instruction.opcode.mnemonic,
+                        label: label
+                    }));
+                    continue;
+                }
+
+                if param == Param::DataLabel {
+                    data_labels.insert(label as u16);
+                }
+
+                let target = offsets.binary_search(&(function_offsets[label as usize] as usize)).unwrap();
+                references[index].push(target);
+            }
+        }
+    }
+
+    // Functions are reachable from label 0 and the entry labels, through references and by continuing into the next
+    // function.
+    let mut reachable = vec![false; functions.len()];
+    let mut queue: Vec<usize> = functions.iter()
+        .enumerate()
+        .filter(|&(_, f)| f.labels.iter().any(|label| *label == 0 || entry_labels.contains(label)))
+        .map(|(index, _)| index)
+        .collect();
+
+    while let Some(index) = queue.pop() {
+        if reachable[index] {
+            continue;
+        }
+
+        reachable[index] = true;
+        queue.extend(references[index].iter().cloned());
+
+        if functions[index].falls_through() && index + 1 < functions.len() {
+            queue.push(index + 1);
+        }
+    }
+
+    for (index, function) in functions.iter().enumerate() {
+        // Problems in data were only found because it was decoded as code.
+        if function.labels.iter().any(|label| data_labels.contains(label)) {
+            continue;
+        }
+
+        if let Some((offset, ref kind)) = function.problem {
+            problems.push((offset, function.label(), kind.clone()));
+        }
+
+        if !reachable[index] && !function.labels.is_empty() {
+            problems.push((function.start, function.label(), ProblemKind::Unreachable));
+        }
+    }
+
+    let mut problems: Vec<Problem> = problems.into_iter()
+        .map(|(offset, label, kind)| Problem { offset: object_code_offset + offset, label: label, kind: kind })
+        .collect();
+    problems.sort_by_key(|problem| problem.offset);
+    return Ok(problems);
+}
+
+fn read_function(object_code: &[u8], start: usize, end: usize, labels: Vec<u16>, text_encoding: TextEncoding)
+                 -> Function {
+    let data = &object_code[start..end];
+    let mut cursor = Cursor::new(data);
+    let mut instructions = Vec::new();
+    let mut problem = None;
+
+    while (cursor.position() as usize) < data.len() {
+        let offset = start + cursor.position() as usize;
+
+        match code::read_instruction(&mut cursor, text_encoding) {
+            Ok(Some(instruction)) => instructions.push((offset, instruction)),
+            Ok(None) => {
+                let code = match data[offset - start] {
+                    prefix @ 0xF8 | prefix @ 0xF9 => ((prefix as u16) << 8) | data[offset - start + 1] as u16,
+                    code => code as u16
+                };
+                problem = Some((offset, match opcodes::find(code) {
+                    Some(opcode) => ProblemKind::InvalidArgument { mnemonic: opcode.mnemonic },
+                    None => ProblemKind::UnknownOpcode(code)
+                }));
+                break;
+            },
+            Err(_) => {
+                problem = Some((offset, ProblemKind::TruncatedInstruction));
+                break;
+            }
+        }
+    }
+
+    return Function { start: start, labels: labels, instructions: instructions, problem: problem };
+}
+
+#[cfg(test)]
+mod tests {
+    use script::{self, asm};
+    use super::{validate, ProblemKind};
+
+    #[test]
+    fn entry_labels_make_functions_reachable() {
+        let script = asm::assemble(".version pc\n.label_count 2\n0:\nret\n1:\nret\n").unwrap();
+        let data = script::write(&script).unwrap();
+
+        let problems = validate(&data, &[]).unwrap();
+        assert_eq!(problems.len(), 1);
+        assert_eq!(problems[0].label, Some(1));
+        assert_eq!(problems[0].kind, ProblemKind::Unreachable);
+        assert!(problems[0].kind.is_warning());
+
+        assert!(validate(&data, &[1]).unwrap().is_empty());
+    }
+
+    #[test]
+    fn va_call_takes_any_number_of_arguments() {
+        let text = [".version pc", ".label_count 2", "0:", "va_start", "arg_pushl 1", "arg_pushr r1", "va_call 1",
+                    "va_end", "ret", "1:", "ret"].join("\n");
+        let data = script::write(&asm::assemble(&text).unwrap()).unwrap();
+
+        assert!(validate(&data, &[]).unwrap().is_empty());
+    }
+}
@@ -0,0 +1,13 @@
+// Generated data for tests and benchmarks.
+
+// Returns size pseudo-random bytes from an xorshift generator, which barely compress. Seed must not be 0.
+pub fn noise(size: usize, mut seed: u32) -> Vec<u8> {
+    (0..size)
+        .map(|_| {
+            seed ^= seed << 13;
+            seed ^= seed >> 17;
+            seed ^= seed << 5;
+            seed as u8
+        })
+        .collect()
+}
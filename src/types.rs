@@ -1,11 +1,138 @@
 use std::fmt::{self, Debug};
-use std::collections::BTreeMap;
+use std::collections::{BTreeMap, BTreeSet};
+use prs::DecompressStats;
 
 pub struct Quest {
+    // Unknown for quests read from a standalone .dat file.
+    pub version: Option<Version>,
+    // The following fields are read from the .bin file and are empty when there is none.
+    pub quest_number: Option<u16>,
+    pub language: Option<Language>,
+    // Offsets in and size of the decompressed .bin file, the object code runs until the function offset table.
+    pub object_code_offset: Option<u32>,
+    pub function_offset_table_offset: Option<u32>,
+    pub bin_size: Option<u32>,
     pub name: String,
     pub short_description: String,
+    pub long_description: String,
     pub episode: Episode,
-    pub monster_counts: BTreeMap<MonsterType, u32>
+    pub episode_source: EpisodeSource,
+    // Whether a Dreamcast/GameCube quest is known to be made for GameCube, see bin::read.
+    pub gamecube: bool,
+    // Monsters that spawn, monsters in waves that no event spawns aren't counted.
+    pub monster_counts: BTreeMap<MonsterType, u32>,
+    // The monster counts split up by area and by section.
+    pub area_monster_counts: BTreeMap<u32, BTreeMap<MonsterType, u32>>,
+    pub section_monster_counts: BTreeMap<Section, BTreeMap<MonsterType, u32>>,
+    // Every monster of the .dat file by wave, including the unspawned waves.
+    pub wave_monster_counts: BTreeMap<Wave, BTreeMap<MonsterType, u32>>,
+    // Waves with monsters that no event spawns, they're usually leftover placements.
+    pub unspawned_waves: BTreeSet<Wave>,
+    pub object_counts: BTreeMap<ObjectType, u32>,
+    // Events of the .dat file, they spawn the waves of monsters.
+    pub events: Vec<Event>,
+    // Maps loaded by the script's initialization function.
+    pub map_designations: Vec<AreaVariant>,
+    // Strings in which invalid units were replaced.
+    pub replaced_text: Vec<ReplacedText>,
+    pub qst_headers: Vec<QstHeader>,
+    // Describe how the .dat and .bin files were decompressed, trailing data after the end of a file can be a sign of
+    // corruption. None for files that weren't compressed.
+    pub dat_compression: Option<DecompressStats>,
+    pub bin_compression: Option<DecompressStats>
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Language {
+    Japanese,
+    English,
+    German,
+    French,
+    Spanish,
+    SimplifiedChinese,
+    TraditionalChinese,
+    Korean,
+    Unknown(u8)
+}
+
+impl From<u8> for Language {
+    fn from(value: u8) -> Language {
+        use self::Language::*;
+
+        match value {
+            0 => Japanese,
+            1 => English,
+            2 => German,
+            3 => French,
+            4 => Spanish,
+            5 => SimplifiedChinese,
+            6 => TraditionalChinese,
+            7 => Korean,
+            _ => Unknown(value)
+        }
+    }
+}
+
+impl From<Language> for u8 {
+    fn from(language: Language) -> u8 {
+        use self::Language::*;
+
+        match language {
+            Japanese => 0,
+            English => 1,
+            German => 2,
+            French => 3,
+            Spanish => 4,
+            SimplifiedChinese => 5,
+            TraditionalChinese => 6,
+            Korean => 7,
+            Unknown(value) => value
+        }
+    }
+}
+
+impl fmt::Display for Language {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        use self::Language::*;
+
+        let name = match *self {
+            Japanese => "Japanese",
+            English => "English",
+            German => "German",
+            French => "French",
+            Spanish => "Spanish",
+            SimplifiedChinese => "Simplified Chinese",
+            TraditionalChinese => "Traditional Chinese",
+            Korean => "Korean",
+            Unknown(value) => return write!(f, "Unknown ({})", value)
+        };
+
+        write!(f, "{}", name)
+    }
+}
+
+// Describes a file embedded in a .qst file.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct QstHeader {
+    pub quest_type: QuestType,
+    pub quest_number: u16,
+    pub quest_name: String,
+    pub file_name: String,
+    pub size: u32
+}
+
+// Online quests are sent by the server when they're started, download quests are saved to a memory card or disk
+// first.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum QuestType {
+    Online,
+    Download
+}
+
+impl fmt::Display for QuestType {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        Debug::fmt(self, f)
+    }
 }
 
 #[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Debug)]
@@ -21,6 +148,308 @@ impl fmt::Display for Episode {
     }
 }
 
+// How invalid units in strings are handled.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum TextMode {
+    // Invalid units are replaced with U+FFFD and reported in ReplacedText.
+    Lenient,
+    // Invalid units are a ReadError::Encoding.
+    Strict
+}
+
+// Invalid units that were replaced in a string field. Offset is the position of the first invalid unit within the
+// file the string was read from, for embedded files that's the decompressed file.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct ReplacedText {
+    pub field: &'static str,
+    pub offset: u64,
+    pub count: usize
+}
+
+// A map variant of an area. Area numbers are per episode.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct AreaVariant {
+    pub area: u32,
+    pub variant: u32
+}
+
+// A monster or NPC from the NPC table of a .dat file, each is a 72 byte record. Area is the area of the table the
+// record is in, the other fields are in the order of the record.
+#[derive(Clone, PartialEq, Debug)]
+pub struct Npc {
+    pub type_id: u16,
+    // Unknown fields at offset 2 to 12.
+    pub unknown: [u16; 5],
+    pub section: u16,
+    pub wave: u16,
+    // Unknown, often equal to wave.
+    pub wave2: u32,
+    pub position: [f32; 3],
+    // Rotation around the x, y and z axis, 0x10000 is a full turn.
+    pub rotation: [i32; 3],
+    pub scale: [f32; 3],
+    // Meaning depends on the type, for NPCs it's the NPC ID.
+    pub param4: f32,
+    // Label of the script function that runs when the player talks to an NPC. Stored as a float.
+    pub script_label: u32,
+    // Selects the variant of a monster.
+    pub skin: u32,
+    pub param7: u32,
+    pub area: u32
+}
+
+impl Npc {
+    // Monsters with a rare variant that isn't selected by the skin use bit 23 of the second scale field, which holds
+    // flags instead of a scale for them.
+    pub fn regular(&self) -> bool {
+        self.scale[1].to_bits() & 0x800000 == 0
+    }
+}
+
+// An object from the object table of a .dat file, each is a 68 byte record. Area is the area of the table the record
+// is in, the other fields are in the order of the record.
+#[derive(Clone, PartialEq, Debug)]
+pub struct Object {
+    pub type_id: u16,
+    // Unknown fields at offset 2 to 12.
+    pub unknown: [u16; 5],
+    pub section: u16,
+    pub unknown2: u16,
+    pub position: [f32; 3],
+    // Rotation around the x, y and z axis, 0x10000 is a full turn.
+    pub rotation: [i32; 3],
+    // Type specific parameters, the float parameters are usually a scale. E.g. doors and switches store their switch
+    // flag in the integer parameters.
+    pub float_params: [f32; 3],
+    pub int_params: [u32; 4],
+    pub area: u32
+}
+
+impl Object {
+    pub fn object_type(&self) -> ObjectType {
+        ObjectType::from(self.type_id)
+    }
+}
+
+// Object types that matter when auditing quests, other types are Unknown with their type ID.
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Debug)]
+pub enum ObjectType {
+    PlayerSet,
+    Teleporter,
+    Warp,
+    BossTeleporter,
+    QuestWarp,
+    TouchPlate,
+    ForestDoor,
+    ForestSwitch,
+    LaserFence,
+    LaserSquareFence,
+    ForestLaserFenceSwitch,
+    RandomBox,
+    BlackSlidingDoor,
+    RicoMessagePod,
+    SwitchNoneDoor,
+    EnemyBoxGrey,
+    FixedBox,
+    EnemyBoxBrown,
+    EmptyBox,
+    FloorPanel,
+    Caves4ButtonDoor,
+    CavesNormalDoor,
+    CavesSwitchDoor,
+    Unknown(u16)
+}
+
+impl From<u16> for ObjectType {
+    fn from(type_id: u16) -> ObjectType {
+        use self::ObjectType::*;
+
+        match type_id {
+            0 => PlayerSet,
+            2 => Teleporter,
+            3 => Warp,
+            25 => BossTeleporter,
+            27 => QuestWarp,
+            34 => TouchPlate,
+            128 => ForestDoor,
+            129 => ForestSwitch,
+            130 => LaserFence,
+            131 => LaserSquareFence,
+            132 => ForestLaserFenceSwitch,
+            136 => RandomBox,
+            140 => BlackSlidingDoor,
+            141 => RicoMessagePod,
+            144 => SwitchNoneDoor,
+            145 => EnemyBoxGrey,
+            146 => FixedBox,
+            147 => EnemyBoxBrown,
+            149 => EmptyBox,
+            192 => FloorPanel,
+            193 => Caves4ButtonDoor,
+            194 => CavesNormalDoor,
+            206 => CavesSwitchDoor,
+            _ => Unknown(type_id)
+        }
+    }
+}
+
+impl fmt::Display for ObjectType {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        use self::ObjectType::*;
+
+        let name = match *self {
+            PlayerSet => "Player Set",
+            Teleporter => "Teleporter",
+            Warp => "Warp",
+            BossTeleporter => "Boss Teleporter",
+            QuestWarp => "Quest Warp",
+            TouchPlate => "Touch Plate",
+            ForestDoor => "Forest Door",
+            ForestSwitch => "Forest Switch",
+            LaserFence => "Laser Fence",
+            LaserSquareFence => "Laser Square Fence",
+            ForestLaserFenceSwitch => "Forest Laser Fence Switch",
+            RandomBox => "Random Box",
+            BlackSlidingDoor => "Black Sliding Door",
+            RicoMessagePod => "Rico Message Pod",
+            SwitchNoneDoor => "Switch None Door",
+            EnemyBoxGrey => "Enemy Box (Grey)",
+            FixedBox => "Fixed Box",
+            EnemyBoxBrown => "Enemy Box (Brown)",
+            EmptyBox => "Empty Box",
+            FloorPanel => "Floor Panel",
+            Caves4ButtonDoor => "Caves 4 Button Door",
+            CavesNormalDoor => "Caves Normal Door",
+            CavesSwitchDoor => "Caves Switch Door",
+            Unknown(type_id) => return write!(f, "Unknown ({})", type_id)
+        };
+
+        write!(f, "{}", name)
+    }
+}
+
+// An event from the event table of a .dat file. Events spawn a wave of monsters in a section and run their actions
+// after the wave is cleared and the delay has passed. Area is the area of the table the event is in.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct Event {
+    pub id: u32,
+    pub flags: u16,
+    pub event_type: u16,
+    pub section: u16,
+    pub wave: u16,
+    // In frames, 30 per second.
+    pub delay: u32,
+    pub actions: Vec<EventAction>,
+    pub area: u32
+}
+
+// A section of an area, areas are divided into sections that are mostly single rooms.
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Debug)]
+pub struct Section {
+    pub area: u32,
+    pub section: u16
+}
+
+impl fmt::Display for Section {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Area {} section {}", self.area, self.section)
+    }
+}
+
+// A wave of monsters in a section of an area, identified like events identify the wave they spawn.
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Debug)]
+pub struct Wave {
+    pub area: u32,
+    pub section: u16,
+    pub wave: u16
+}
+
+impl fmt::Display for Wave {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Area {} section {} wave {}", self.area, self.section, self.wave)
+    }
+}
+
+// Event actions, the action list ends with an end action that isn't included. Actions with an unknown opcode end the
+// list too, since their size isn't known.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum EventAction {
+    Nop,
+    SpawnObjects { section: u16, group: u16 },
+    SpawnNpcs { section: u16, wave: u16 },
+    // Sets or clears a switch flag, which opens or closes the doors that use it.
+    Unlock { switch_flag: u16 },
+    Lock { switch_flag: u16 },
+    TriggerEvent { event_id: u32 },
+    // Spawns a wave like SpawnNpcs, its exact effect isn't known.
+    SpawnNpcsStop { section: u16, wave: u16 },
+    // Calls the script function with the label.
+    CallFunction { label: u32 },
+    Unknown(u8)
+}
+
+impl fmt::Display for EventAction {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            EventAction::Nop => write!(f, "nop"),
+            EventAction::SpawnObjects { section, group } =>
+                write!(f, "spawn objects in section {} group {}", section, group),
+            EventAction::SpawnNpcs { section, wave } => write!(f, "spawn section {} wave {}", section, wave),
+            EventAction::Unlock { switch_flag } => write!(f, "unlock switch flag {}", switch_flag),
+            EventAction::Lock { switch_flag } => write!(f, "lock switch flag {}", switch_flag),
+            EventAction::TriggerEvent { event_id } => write!(f, "trigger event {}", event_id),
+            EventAction::SpawnNpcsStop { section, wave } =>
+                write!(f, "spawn section {} wave {} (stop)", section, wave),
+            EventAction::CallFunction { label } => write!(f, "call function {}", label),
+            EventAction::Unknown(opcode) => write!(f, "unknown action {:#04x}", opcode)
+        }
+    }
+}
+
+// How the episode of a quest was determined.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum EpisodeSource {
+    // The set_episode opcode in the script's initialization function.
+    Script,
+    // The kinds of monsters and the areas they're in.
+    Monsters,
+    // Neither gave an answer, so episode I is assumed.
+    Default
+}
+
+impl fmt::Display for EpisodeSource {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let description = match *self {
+            EpisodeSource::Script => "set by the script",
+            EpisodeSource::Monsters => "detected from monsters",
+            EpisodeSource::Default => "default"
+        };
+
+        write!(f, "{}", description)
+    }
+}
+
+// Game version a quest was made for, the file formats differ slightly between versions.
+// Dreamcast and GameCube files use the same formats, Quest::gamecube tells them apart where possible.
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Debug)]
+pub enum Version {
+    DCGC,
+    PC,
+    BB
+}
+
+impl fmt::Display for Version {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match *self {
+            Version::DCGC => "Dreamcast/GameCube",
+            Version::PC => "PC",
+            Version::BB => "Blue Burst"
+        };
+
+        write!(f, "{}", name)
+    }
+}
+
 #[repr(u8)]
 #[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Debug)]
 pub enum MonsterType {
@@ -273,4 +702,4 @@ impl fmt::Display for MonsterType {
 
         write!(f, "{}", name)
     }
-}
\ No newline at end of file
+}
@@ -1,16 +1,155 @@
-use std::borrow::Cow;
-use encoding::{self, Encoding, DecoderTrap};
+use encoding::{self, Encoding, EncodingRef, EncoderTrap};
+use read::{self, ReadError};
+use types::{Language, ReplacedText, TextMode, Version};
+use write::WriteError;
 
-pub fn read_ascii_string(buffer: &[u8]) -> Result<String, Cow<'static, str>> {
-    let string_end = buffer.iter().position(|&b| { b == 0 }).unwrap_or(buffer.len());
-    return Ok(encoding::all::ASCII.decode(&buffer[0..string_end], DecoderTrap::Ignore)?);
+// Encoding of the strings in .bin files.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum TextEncoding {
+    Ascii,
+    ShiftJis,
+    Iso8859_1,
+    Utf16Le
 }
 
-pub fn read_utf_16le_string(buffer: &[u8]) -> Result<String, Cow<'static, str>> {
-    let string_end = buffer[0..(buffer.len() / 2 * 2)]
-        .chunks(2)
-        .position(|v| { v[0] == 0 && v[1] == 0 })
-        .map(|x| { 2 * x })
-        .unwrap_or(buffer.len());
-    return Ok(encoding::all::UTF_16LE.decode(&buffer[0..string_end], DecoderTrap::Ignore)?);
+impl TextEncoding {
+    // Dreamcast and GameCube quests use Shift-JIS when they're Japanese and ISO-8859-1 otherwise, PC and Blue Burst
+    // quests use UTF-16.
+    pub fn for_quest(version: Version, language: Language) -> TextEncoding {
+        match version {
+            Version::DCGC => TextEncoding::for_language(language),
+            _ => TextEncoding::Utf16Le
+        }
+    }
+
+    // The single byte encoding of a language, Shift-JIS for Japanese and ISO-8859-1 otherwise. The quest names in
+    // .qst headers use it for every version.
+    pub fn for_language(language: Language) -> TextEncoding {
+        match language {
+            Language::Japanese => TextEncoding::ShiftJis,
+            _ => TextEncoding::Iso8859_1
+        }
+    }
+
+    pub fn terminator_size(self) -> usize {
+        if self == TextEncoding::Utf16Le { 2 } else { 1 }
+    }
+
+    pub fn encoding(self) -> EncodingRef {
+        match self {
+            TextEncoding::Ascii => encoding::all::ASCII,
+            // Windows-31J is the Shift-JIS variant that includes the NEC and IBM extensions.
+            TextEncoding::ShiftJis => encoding::all::WINDOWS_31J,
+            TextEncoding::Iso8859_1 => encoding::all::ISO_8859_1,
+            TextEncoding::Utf16Le => encoding::all::UTF_16LE
+        }
+    }
+}
+
+// Decodes null terminated strings. In strict mode invalid units are an error, in lenient mode they're replaced with
+// U+FFFD and recorded in replaced.
+pub struct TextDecoder {
+    pub mode: TextMode,
+    pub replaced: Vec<ReplacedText>
+}
+
+impl TextDecoder {
+    pub fn new(mode: TextMode) -> TextDecoder {
+        TextDecoder { mode: mode, replaced: Vec::new() }
+    }
+
+    // Offset is the position of buffer in its file and field the name used in errors, together they locate invalid
+    // units.
+    pub fn decode(&mut self, buffer: &[u8], text_encoding: TextEncoding, field: &'static str, offset: u64)
+                  -> read::Result<String> {
+        let bytes = &buffer[0..string_end(buffer, text_encoding)];
+        let mut decoder = text_encoding.encoding().raw_decoder();
+        let mut string = String::new();
+        let mut replaced: Option<ReplacedText> = None;
+        let mut remaining = 0;
+
+        // Same loop as Encoding::decode_to, which doesn't tell where the errors are.
+        loop {
+            let (processed, feed_error) = decoder.raw_feed(&bytes[remaining..], &mut string);
+            let unprocessed = remaining + processed;
+            let (error, finished) = match feed_error {
+                Some(error) => (error, false),
+                None => match decoder.raw_finish(&mut string) {
+                    Some(error) => {
+                        remaining = bytes.len();
+                        (error, true)
+                    },
+                    None => break
+                }
+            };
+            remaining = (remaining as isize + error.upto) as usize;
+            let error_offset = offset + unprocessed as u64;
+
+            if self.mode == TextMode::Strict {
+                return Err(ReadError::Encoding { field: field, offset: error_offset });
+            }
+
+            string.push('\u{FFFD}');
+            replaced.get_or_insert(ReplacedText { field: field, offset: error_offset, count: 0 }).count += 1;
+
+            if finished && remaining >= bytes.len() {
+                break;
+            }
+        }
+
+        if let Some(replaced) = replaced {
+            self.replaced.push(replaced);
+        }
+
+        return Ok(string);
+    }
+}
+
+// Returns the length of the string in buffer without null terminator.
+fn string_end(buffer: &[u8], text_encoding: TextEncoding) -> usize {
+    if text_encoding.terminator_size() == 2 {
+        buffer[0..(buffer.len() / 2 * 2)]
+            .chunks(2)
+            .position(|v| { v[0] == 0 && v[1] == 0 })
+            .map(|x| { 2 * x })
+            .unwrap_or(buffer.len())
+    } else {
+        // Null bytes can't occur in multi-byte Shift-JIS characters, so the first null byte ends the string.
+        buffer.iter().position(|&b| { b == 0 }).unwrap_or(buffer.len())
+    }
+}
+
+// Writes a null terminated string to buffer, the rest of buffer is filled with zeroes. Field is the name used in
+// errors.
+pub fn write_ascii_string(string: &str, buffer: &mut [u8], field: &'static str) -> Result<(), WriteError> {
+    let encoded = encoding::all::ASCII.encode(string, EncoderTrap::Strict)?;
+
+    if encoded.len() >= buffer.len() {
+        return Err(WriteError::StringTooLong { field: field, max_size: buffer.len() });
+    }
+
+    for byte in buffer.iter_mut() {
+        *byte = 0;
+    }
+
+    buffer[0..encoded.len()].copy_from_slice(&encoded);
+    return Ok(());
+}
+
+// Writes a null terminated string in the given encoding to buffer, the rest of buffer is filled with zeroes. Field is
+// the name used in errors.
+pub fn write_string(string: &str, buffer: &mut [u8], text_encoding: TextEncoding, field: &'static str)
+                    -> Result<(), WriteError> {
+    let encoded = text_encoding.encoding().encode(string, EncoderTrap::Strict)?;
+
+    if encoded.len() + text_encoding.terminator_size() > buffer.len() {
+        return Err(WriteError::StringTooLong { field: field, max_size: buffer.len() });
+    }
+
+    for byte in buffer.iter_mut() {
+        *byte = 0;
+    }
+
+    buffer[0..encoded.len()].copy_from_slice(&encoded);
+    return Ok(());
 }
@@ -0,0 +1,45 @@
+pub mod qst;
+
+use std::borrow::Cow;
+use std::fmt;
+use std::io;
+use std::result;
+
+pub type Result<T> = result::Result<T, WriteError>;
+
+#[derive(Debug)]
+pub enum WriteError {
+    Io(io::Error),
+    Encoding(Cow<'static, str>),
+    // A string doesn't fit in its field, max_size includes the null terminator.
+    StringTooLong { field: &'static str, max_size: usize },
+    // A number doesn't fit in its field.
+    NumberTooLarge { field: &'static str, max: u32 },
+    // The arguments of a script instruction don't match the parameters of its opcode.
+    InvalidArgument { mnemonic: &'static str }
+}
+
+impl fmt::Display for WriteError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            WriteError::Io(ref err) => write!(f, "{}", err),
+            WriteError::Encoding(ref message) => write!(f, "{}", message),
+            WriteError::StringTooLong { field, max_size } =>
+                write!(f, "the {} doesn't fit in {} bytes with its terminator", field, max_size),
+            WriteError::NumberTooLarge { field, max } => write!(f, "the {} is larger than {}", field, max),
+            WriteError::InvalidArgument { mnemonic } => write!(f, "invalid arguments for {}", mnemonic)
+        }
+    }
+}
+
+impl From<io::Error> for WriteError {
+    fn from(err: io::Error) -> WriteError {
+        WriteError::Io(err)
+    }
+}
+
+impl From<Cow<'static, str>> for WriteError {
+    fn from(err: Cow<'static, str>) -> WriteError {
+        WriteError::Encoding(err)
+    }
+}
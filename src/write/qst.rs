@@ -0,0 +1,210 @@
+use std::io::{Cursor, Write};
+use byteorder::{WriteBytesExt, LittleEndian};
+use prs::{self, Compression};
+use types::{Language, QuestType, Version};
+use util::{self, write_ascii_string, TextEncoding};
+use write::{self, WriteError};
+
+// Describes the .qst file to write.
+pub struct QstOptions {
+    pub version: Version,
+    pub quest_type: QuestType,
+    pub quest_number: u16,
+    // Name stored in the headers, at most 31 bytes or 23 bytes for Blue Burst. Longer names are cut off.
+    pub quest_name: String,
+    // Language of the quest, selects the encoding of the quest name.
+    pub language: Language,
+    // Name of the embedded files without extension, at most 11 bytes.
+    pub file_name: String,
+    pub compression: Compression
+}
+
+// Low level write method for .qst files.
+// Compresses the given .dat and .bin data and writes a header for each followed by their interleaved chunks, the
+// layout qst::read expects.
+pub fn write<W: Write>(dst: &mut W, options: &QstOptions, dat: &[u8], bin: &[u8]) -> write::Result<()> {
+    if options.version != Version::BB && options.quest_number > 0xFF {
+        return Err(WriteError::NumberTooLarge { field: "quest number", max: 0xFF });
+    }
+
+    let files = [
+        (format!("{}.dat", options.file_name), compress(dat, options.compression)?),
+        (format!("{}.bin", options.file_name), compress(bin, options.compression)?)
+    ];
+
+    for &(ref file_name, ref data) in &files {
+        write_header(dst, options, file_name, data.len())?;
+    }
+
+    // Files are interleaved chunk by chunk, the chunk index is stored in the flags field of the packet header.
+    let chunk_count = files.iter().map(|&(_, ref data)| (data.len() + 1023) / 1024).max().unwrap_or(0);
+
+    if options.version != Version::BB && chunk_count > 0x100 {
+        return Err(WriteError::NumberTooLarge { field: "chunk index", max: 0xFF });
+    }
+
+    for index in 0..chunk_count {
+        for &(ref file_name, ref data) in &files {
+            let start = index * 1024;
+
+            if start < data.len() {
+                let end = ::std::cmp::min(start + 1024, data.len());
+                write_chunk(dst, options, index as u32, file_name, &data[start..end])?;
+            }
+        }
+    }
+
+    return Ok(());
+}
+
+fn compress(data: &[u8], compression: Compression) -> write::Result<Vec<u8>> {
+    let mut compressed = Vec::new();
+    prs::compress(&mut Cursor::new(data), &mut compressed, compression)?;
+    return Ok(compressed);
+}
+
+// Writes the packet header fields in the order of the version.
+fn write_packet_header<W: Write>(dst: &mut W, version: Version, command: u16, flags: u32, size: u16)
+                                -> write::Result<()> {
+    match version {
+        Version::DCGC => {
+            dst.write_u8(command as u8)?;
+            dst.write_u8(flags as u8)?;
+            dst.write_u16::<LittleEndian>(size)?;
+        },
+        Version::PC => {
+            dst.write_u16::<LittleEndian>(size)?;
+            dst.write_u8(command as u8)?;
+            dst.write_u8(flags as u8)?;
+        },
+        Version::BB => {
+            dst.write_u16::<LittleEndian>(size)?;
+            dst.write_u16::<LittleEndian>(command)?;
+            dst.write_u32::<LittleEndian>(flags)?;
+        }
+    }
+
+    return Ok(());
+}
+
+fn write_header<W: Write>(dst: &mut W, options: &QstOptions, file_name: &str, size: usize) -> write::Result<()> {
+    let command = match options.quest_type {
+        QuestType::Online => 0x44,
+        QuestType::Download => 0xA6
+    };
+    let mut file_name_buffer = [0; 16];
+    write_ascii_string(file_name, &mut file_name_buffer, "file name")?;
+
+    match options.version {
+        Version::DCGC | Version::PC => {
+            let mut quest_name_buffer = [0; 32];
+            write_quest_name(&options.quest_name, &mut quest_name_buffer, options.language)?;
+
+            write_packet_header(dst, options.version, command, options.quest_number as u32, 0x3C)?;
+            dst.write_all(&quest_name_buffer)?;
+            dst.write_all(&[0; 4])?;
+            dst.write_all(&file_name_buffer)?;
+            dst.write_u32::<LittleEndian>(size as u32)?;
+        },
+        Version::BB => {
+            let mut quest_name_buffer = [0; 24];
+            write_quest_name(&options.quest_name, &mut quest_name_buffer, options.language)?;
+
+            write_packet_header(dst, options.version, command, options.quest_number as u32, 0x58)?;
+            dst.write_all(&[0; 36])?;
+            dst.write_all(&file_name_buffer)?;
+            dst.write_u32::<LittleEndian>(size as u32)?;
+            dst.write_all(&quest_name_buffer)?;
+        }
+    }
+
+    return Ok(());
+}
+
+// Writes the quest name in the encoding qst::read uses for the language, characters that don't fit are left out.
+fn write_quest_name(quest_name: &str, buffer: &mut [u8], language: Language) -> write::Result<()> {
+    let text_encoding = TextEncoding::for_language(language);
+    let mut name = quest_name;
+
+    loop {
+        match util::write_string(name, buffer, text_encoding, "quest name") {
+            Err(WriteError::StringTooLong { .. }) => {
+                let last_char_start = name.char_indices().last().map(|(index, _)| index).unwrap_or(0);
+                name = &name[0..last_char_start];
+            },
+            result => return result
+        }
+    }
+}
+
+fn write_chunk<W: Write>(dst: &mut W, options: &QstOptions, index: u32, file_name: &str, data: &[u8])
+                        -> write::Result<()> {
+    let command = match options.quest_type {
+        QuestType::Online => 0x13,
+        QuestType::Download => 0xA7
+    };
+    let (size, padding) = match options.version {
+        Version::DCGC | Version::PC => (1048, 0),
+        Version::BB => (1056, 4)
+    };
+    let mut file_name_buffer = [0; 16];
+    write_ascii_string(file_name, &mut file_name_buffer, "file name")?;
+    let mut data_buffer = [0; 1024];
+    data_buffer[0..data.len()].copy_from_slice(data);
+
+    write_packet_header(dst, options.version, command, index, size)?;
+    dst.write_all(&file_name_buffer)?;
+    dst.write_all(&data_buffer)?;
+    dst.write_u32::<LittleEndian>(data.len() as u32)?;
+    dst.write_all(&[0; 4][0..padding])?;
+    return Ok(());
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+    use prs::{self, Compression};
+    use read::qst;
+    use types::{Language, QuestType, TextMode, Version};
+    use test_data::noise;
+    use util::TextDecoder;
+    use super::*;
+
+    #[test]
+    fn written_files_are_extracted_unchanged() {
+        for &version in &[Version::DCGC, Version::PC, Version::BB] {
+            // Data that doesn't compress well, so the files span several chunks.
+            let dat = noise(3000, 1);
+            let mut bin = noise(1500, 2);
+            // The language field of the .bin header.
+            bin[if version == Version::BB { 0x14 } else { 0x10 }] = u8::from(Language::French);
+            let options = QstOptions {
+                version: version,
+                quest_type: QuestType::Download,
+                quest_number: 58,
+                quest_name: "Quête de test avec un nom beaucoup trop long".to_string(),
+                language: Language::French,
+                file_name: "quest58".to_string(),
+                compression: Compression::Fast
+            };
+            let mut data = Vec::new();
+            write(&mut data, &options, &dat, &bin).unwrap();
+
+            let mut decoder = TextDecoder::new(TextMode::Strict);
+            let (read_version, headers, file_data) = qst::extract(&mut Cursor::new(data), &mut decoder).unwrap();
+            let decompressed: Vec<Vec<u8>> = file_data.iter()
+                .map(|data| prs::decompress_buf(data, prs::DEFAULT_MAX_SIZE).unwrap().0)
+                .collect();
+            let file_names: Vec<&str> = headers.iter().map(|h| &h.file_name[..]).collect();
+            // ISO-8859-1 uses a byte per character, the name is cut off to fit in the header.
+            let name_size = if version == Version::BB { 23 } else { 31 };
+            let quest_name: String = options.quest_name.chars().take(name_size).collect();
+
+            assert_eq!(read_version, version);
+            assert_eq!(file_names, vec!["quest58.dat", "quest58.bin"]);
+            assert!(headers.iter().all(|h| h.quest_type == QuestType::Download && h.quest_number == 58));
+            assert!(headers.iter().all(|h| h.quest_name == quest_name));
+            assert_eq!(decompressed, vec![dat, bin]);
+        }
+    }
+}